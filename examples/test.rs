@@ -2,7 +2,7 @@
 #![feature(duration_millis_float)]
 #![feature(slice_pattern)]
 #![feature(new_uninit)]
-#![feature(maybe_uninit_uninit_array)]
+
 #![feature(maybe_uninit_slice)]
 
 use std::time::Instant;
@@ -26,6 +26,7 @@ pub fn computation() {
     add_traffic_lights(FileLoader::new("cache\\traffic.dat").load().unwrap());
     stop_watch.elapsed_store("Traffic Data to Memory");
     add_suburbs(FileLoader::new("cache\\suburb.dat").load().unwrap());
+    build_suburb_tree();
     stop_watch.elapsed_store("Suburb Data to Memory");
     add_nodes(FileLoader::new("cache\\nodes.dat").load_parallel().unwrap());
     stop_watch.elapsed_store("Node Data To Memory");
@@ -38,7 +39,7 @@ pub fn computation() {
     stop_watch.elapsed_store("Memory Read Data");
 
     let start_time_map = Instant::now();
-    let _results: Vec<(jint, jint)> = compute(geometries, traffic_lights);
+    let _results: Vec<(jint, jint)> = compute(get_suburb_tree(), traffic_lights);
     stop_watch.elapsed_store("Computation Total Time");
     let nanos = start_time_map.elapsed().as_nanos() as f64;
     let nano_seconds_per_op = nanos / ((geometries.len() * traffic_lights.len()) as f64);
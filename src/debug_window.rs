@@ -10,7 +10,7 @@ use kiss3d::text::Font;
 use kiss3d::window::Window;
 use rayon::prelude::*;
 
-use crate::{get_node_tree, get_nodes, get_solver, get_suburbs, get_traffic_lights};
+use crate::{get_isochrone, get_node_tree, get_nodes, get_solver, get_suburbs, get_traffic_lights};
 use crate::objects::boundary::Boundary;
 use crate::objects::pathing::node::Node;
 use crate::objects::pathing::node_type::NodeType;
@@ -55,10 +55,14 @@ fn point(x: Pos, y: Pos) -> Point3<f32> {
 
 #[inline]
 fn add_suburb_to_scene(window: &mut Window, geometry: &Suburb) {
+    add_polygon_to_scene(window, geometry, &Point3::new(200f32 / 255f32, 200f32 / 255f32, 200f32 / 255f32));
+}
+
+#[inline]
+fn add_polygon_to_scene(window: &mut Window, geometry: &Suburb, suburb_boundary_color: &Point3<f32>) {
     let x = &geometry.x_points;
     let y = &geometry.y_points;
     let size = x.len() - 1;
-    let suburb_boundary_color = &Point3::new(200f32 / 255f32, 200f32 / 255f32, 200f32 / 255f32);
     for i in 0..size {
         window.draw_line(
             &point(x[i], y[i]),
@@ -100,8 +104,10 @@ fn handle_key_pressed(window: &Window) -> bool {
                 input(window, Key::Key3, true,
                       input(window, Key::Key4, true,
                             input(window, Key::Key5, true,
-                                  input(window, Key::Equals, true,
-                                        input(window, Key::Minus, true, false),
+                                  input(window, Key::Key6, true,
+                                        input(window, Key::Equals, true,
+                                              input(window, Key::Minus, true, false),
+                                        ),
                                   ),
                             ),
                       ),
@@ -110,15 +116,41 @@ fn handle_key_pressed(window: &Window) -> bool {
     )
 }
 
+/// What the window draws and how fast the search runs, switched with the number keys and `=` / `-`.
+struct DebugToggles {
+    search_speed: u32,
+    traffic_lights: bool,
+    suburbs: bool,
+    nodes: bool,
+    path: bool,
+    tree: bool,
+    isochrone: bool,
+    /// Whether a toggle key was held down last frame, so that holding it only switches once.
+    key_pressed: bool
+}
+
+impl Default for DebugToggles {
+    fn default() -> Self {
+        Self {
+            search_speed: 100_000,
+            traffic_lights: true,
+            suburbs: false,
+            nodes: false,
+            path: true,
+            tree: false,
+            isochrone: true,
+            key_pressed: false
+        }
+    }
+}
+
 #[inline]
-fn handle_input(window: &Window, camera: &mut FirstPerson,
-                search_speed: &mut u32,
-                display_traffic_lights: &mut bool,
-                display_suburbs: &mut bool,
-                display_nodes: &mut bool,
-                display_path: &mut bool,
-                display_tree: &mut bool,
-                key_pressed: &mut bool) {
+fn toggle(window: &Window, input_key: Key, value: &mut bool) {
+    *value = input(window, input_key, !*value, *value);
+}
+
+#[inline]
+fn handle_input(window: &Window, camera: &mut FirstPerson, toggles: &mut DebugToggles) {
     let fwd = input_of_key(window, Key::S) - input_of_key(window, Key::W);
     let right = input_of_key(window, Key::D) - input_of_key(window, Key::A);
     let up = input_of_key(window, Key::Space) - input_of_key(window, Key::C);
@@ -128,25 +160,20 @@ fn handle_input(window: &Window, camera: &mut FirstPerson,
     let result = camera.view_transform().rotation.inverse_transform_point(&move_vector) * speed * slow_down;
     camera.translate_mut(&Translation::from(result));
 
-    if *key_pressed {
-        *key_pressed = handle_key_pressed(window);
-    } else {
-        let current = *display_traffic_lights;
-        *display_traffic_lights = input(window, Key::Key1, !current, current);
-        let current = *display_suburbs;
-        *display_suburbs = input(window, Key::Key2, !current, current);
-        let current = *display_nodes;
-        *display_nodes = input(window, Key::Key3, !current, current);
-        let current = *display_path;
-        *display_path = input(window, Key::Key4, !current, current);
-        let current = *display_tree;
-        *display_tree = input(window, Key::Key5, !current, current);
-
-        *search_speed = input(window, Key::Equals, *search_speed * 10, *search_speed);
-        *search_speed = input(window, Key::Minus, u32::max(*search_speed / 10, 1), *search_speed);
+    if !toggles.key_pressed {
+        toggle(window, Key::Key1, &mut toggles.traffic_lights);
+        toggle(window, Key::Key2, &mut toggles.suburbs);
+        toggle(window, Key::Key3, &mut toggles.nodes);
+        toggle(window, Key::Key4, &mut toggles.path);
+        toggle(window, Key::Key5, &mut toggles.tree);
+        toggle(window, Key::Key6, &mut toggles.isochrone);
 
-        *key_pressed = handle_key_pressed(window);
+        let search_speed = toggles.search_speed;
+        toggles.search_speed = input(window, Key::Equals, search_speed * 10, search_speed);
+        let search_speed = toggles.search_speed;
+        toggles.search_speed = input(window, Key::Minus, u32::max(search_speed / 10, 1), search_speed);
     }
+    toggles.key_pressed = handle_key_pressed(window);
 }
 
 #[inline]
@@ -213,43 +240,49 @@ pub fn start_window() {
     let geometries = temp_geo.as_slice();
     timer.elapsed_store("Initial Setup");
 
-    let mut search_speed: u32 = 100_000;
-    let mut display_traffic_lights = true;
-    let mut display_suburbs = false;
-    let mut display_nodes = false;
-    let mut display_path = true;
-    let mut display_tree = false;
-    let mut key_pressed = false;
+    let mut toggles = DebugToggles::default();
     
     timer.disable();
     let solver = get_solver(0);
     while window.render_with_camera(&mut camera) {
         timer.elapsed_store("Render Time");
         timer.print_prefixed("Window");
-        handle_input(&window, &mut camera, &mut search_speed, &mut display_traffic_lights, &mut display_suburbs, &mut display_nodes, &mut display_path, &mut display_tree, &mut key_pressed);
-        get_solver(0).update_search_speed(search_speed);
+        handle_input(&window, &mut camera, &mut toggles);
+        get_solver(0).update_search_speed(toggles.search_speed);
         timer.elapsed_store("Handle Input");
-        if display_traffic_lights {
+        if toggles.traffic_lights {
             traffic_lights.iter().for_each(|x| {
                 add_traffic_light_to_scene(&mut window, x);
             });
             timer.elapsed_store("Traffic Display");
         }
 
-        if display_suburbs {
+        if toggles.suburbs {
             geometries.iter().for_each(|x| {
                 add_suburb_to_scene(&mut window, x);
             });
             timer.elapsed_store("Suburb Display");
         }
 
-        if display_tree {
+        if toggles.tree {
             draw_tree(&mut window, get_node_tree());
             timer.elapsed_store("Tree Display");
         }
 
+        if toggles.isochrone {
+            if let Some(isochrone) = get_isochrone() {
+                let reached_color = &Point3::new(1f32, 1f32, 0f32);
+                let nodes = get_nodes();
+                for index in isochrone.nodes.iter() {
+                    add_graph_node_to_scene(&mut window, nodes.get(*index as usize), reached_color);
+                }
+                add_polygon_to_scene(&mut window, &isochrone.polygon, &Point3::new(1f32, 0.5f32, 0f32));
+                timer.elapsed_store("Isochrone Display");
+            }
+        }
+
         timer.elapsed_store("Path Find");
-        if display_nodes {
+        if toggles.nodes {
             get_nodes()
                 .get_slice()
                 .par_iter()
@@ -266,7 +299,7 @@ pub fn start_window() {
             timer.elapsed_store("Visited Node Display");
         }
         if let Some((path, time, distance)) = get_solver(0).get_path_as_positions() {
-            if display_path {
+            if toggles.path {
                 let destination_color = &Point3::new(1f32, 0f32, 0f32);
                 let start_color = &Point3::new(0f32, 0f32, 1f32);
                 for i in 1..path.len() {
@@ -1,19 +1,28 @@
+use chrono::Utc;
 use jni::JNIEnv;
 use jni::objects::JClass;
-use jni::sys::jint;
+use jni::sys::{jdouble, jint};
 
 use crate::debug_window::{start_search, start_window};
-use crate::get_solver;
+use crate::{add_isochrone, compute_isochrone, get_nodes, get_solver};
 use crate::types::Index;
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNIDebug_launchWindowInternal<'l>(_env: JNIEnv<'l>, _class: JClass<'l>) {
-    get_solver(0).update_search(373729, 37887);
+    get_solver(0).update_search(373729, 37887, Utc::now());
     start_window();
 }
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNIDebug_updateSearch<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, start_node_index : jint, end_node_index : jint) {
-    get_solver(0).update_search(start_node_index as Index, end_node_index as Index);
+    get_solver(0).update_search(start_node_index as Index, end_node_index as Index, Utc::now());
     start_search();
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNIDebug_showIsochrone<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, start_node_index : jint, minutes : jdouble) {
+    let solver = get_solver(0);
+    let isochrone = compute_isochrone(get_nodes().get_slice(), start_node_index as Index, (minutes * 60.0) as u32,
+                                      solver.search_method, solver.cost_model, Utc::now(), solver.utc_offset);
+    add_isochrone(isochrone);
 }
\ No newline at end of file
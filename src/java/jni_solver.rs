@@ -1,25 +1,91 @@
 use std::simd::Simd;
 use std::thread::spawn;
 
-use jni::objects::{JByteArray, JClass, JIntArray, JValue};
-use jni::sys::{jdouble, jint, jobject, jsize};
+use jni::objects::{JByteArray, JClass, JDoubleArray, JIntArray, JMethodID, JObject, JObjectArray, JString, JValue};
+use jni::sys::{jdouble, jdoubleArray, jint, jlong, jobject, jobjectArray, jsize};
 use jni::JNIEnv;
+use chrono::DateTime;
 
 use crate::loader::load_from_bytes;
-use crate::objects::pathing::node_type::SearchMethod;
+use crate::objects::load_shedding::OutageInterval;
+use crate::objects::pathing::cost_model::{CostModel, SpeedSource};
+use crate::objects::pathing::instructions::describe_path;
+use crate::objects::pathing::node_type::{SearchAlgorithm, SearchMethod};
+use crate::objects::pathing::path_segment::PathSegment;
+use crate::objects::pathing::profile::Profile;
+use crate::objects::pathing::route::Route;
+use crate::objects::pathing::stop_order::StopOrder;
 use crate::objects::pathing::solver::Solver;
-use crate::types::{Flag, Pos};
-use crate::{add_nodes, add_solver, associate_traffic_lights_to_nodes, build_node_tree, get_closest_node, get_nodes, get_solver, get_traffic_lights, new_slice, remove_solver};
+use crate::objects::pathing::turns::TurnCosts;
+use crate::types::{Cost, Flag, Index, Pos};
+use rayon::prelude::*;
+
+use crate::{add_nodes, add_solver, add_turn_restrictions, associate_traffic_lights_to_nodes, build_contraction_hierarchy, build_customizable_hierarchy, build_landmarks, build_network_bounds, build_node_tree, build_reverse_graph, build_turn_graph, compute_isochrone, compute_matrix, invalidate_customization, get_closest_node, get_contraction_hierarchy, get_customizable_hierarchy, get_landmarks, get_network_bounds, get_turn_graph, get_nodes, get_reverse_graph, get_solver, get_traffic_lights, new_slice, try_get_traffic_light_tree, remove_solver, set_turn_costs};
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_sendNodes<'l> (env: JNIEnv<'l>, _class: JClass<'l>, data : JByteArray<'l>) {
     let bytes = env.convert_byte_array(&data).expect("Failed to load byte array for traffic lights");
     add_nodes(load_from_bytes(bytes.as_slice()));
-    build_node_tree();
+    let report = build_node_tree();
+    if report.dropped > 0 {
+        println!("Rust Binding - Left {} nodes without a valid position out of the tree", report.dropped);
+    }
+    build_reverse_graph();
+    build_network_bounds();
+}
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_sendTurnRestrictions<'l> (env: JNIEnv<'l>, _class: JClass<'l>, data : JByteArray<'l>) {
+    let bytes = env.convert_byte_array(&data).expect("Failed to load byte array for turn restrictions");
+    add_turn_restrictions(load_from_bytes(bytes.as_slice()));
+    build_turn_graph();
 }
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_setTurnCosts<'l> (_env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                  left : jdouble, right : jdouble, u_turn : jdouble,
+                                                                                  traffic_light_left : jdouble, traffic_light_right : jdouble) {
+    // Costs arrive in seconds, while the solver works in hours.
+    set_turn_costs(Some(TurnCosts {
+        left : (left / 3600.0) as Cost,
+        right : (right / 3600.0) as Cost,
+        u_turn : (u_turn / 3600.0) as Cost,
+        traffic_light_left : (traffic_light_left / 3600.0) as Cost,
+        traffic_light_right : (traffic_light_right / 3600.0) as Cost
+    }));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_useDefaultTurnCosts<'l> (_env: JNIEnv<'l>, _class: JClass<'l>) {
+    set_turn_costs(Some(TurnCosts::default()));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_disableTurnCosts<'l> (_env: JNIEnv<'l>, _class: JClass<'l>) {
+    set_turn_costs(None);
+}
+
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_associateTrafficLightsToNodes<'l> (_env: JNIEnv<'l>, _class: JClass<'l>) {
     associate_traffic_lights_to_nodes();
+    invalidate_customization();
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_loadContractionHierarchy<'l> (mut env: JNIEnv<'l>, _class: JClass<'l>, nodes_file : JString<'l>, search_method : jint) {
+    let nodes_file : String = env.get_string(&nodes_file).expect("Failed to read node file location").into();
+    build_contraction_hierarchy(&nodes_file, SearchMethod::from_id(search_method));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_loadCustomizableHierarchy<'l> (mut env: JNIEnv<'l>, _class: JClass<'l>, nodes_file : JString<'l>, search_method : jint) {
+    let nodes_file : String = env.get_string(&nodes_file).expect("Failed to read node file location").into();
+    build_customizable_hierarchy(&nodes_file, SearchMethod::from_id(search_method));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_loadLandmarks<'l> (mut env: JNIEnv<'l>, _class: JClass<'l>, nodes_file : JString<'l>, count : jint, search_method : jint) {
+    let nodes_file : String = env.get_string(&nodes_file).expect("Failed to read node file location").into();
+    build_landmarks(&nodes_file, count as usize, SearchMethod::from_id(search_method));
 }
 
 #[no_mangle]
@@ -36,66 +102,461 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_updateTrafficLi
         traffic_lights[index].get_mut().flag = *flag as Flag;
     }
     associate_traffic_lights_to_nodes();
+    invalidate_customization();
+}
+
+/// Replaces the outages of every traffic light with intervals in minutes since the Unix epoch. The intervals of
+/// traffic light `i` are `starts[offsets[i]..offsets[i + 1]]` and the matching `ends`, in any order.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_updateTrafficLightOutages<'l> (env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                               offsets : JIntArray<'l>, starts : JIntArray<'l>, ends : JIntArray<'l>) {
+    let read = |array : &JIntArray<'l>| {
+        let mut values = new_slice(0i32, env.get_array_length(array).expect("Failed to read outage array length") as usize);
+        env.get_int_array_region(array, 0, &mut values).expect("Failed to load outage array");
+        values
+    };
+    let (offsets, starts, ends) = (read(&offsets), read(&starts), read(&ends));
+    let traffic_lights = get_traffic_lights().get_slice_mut();
+    for (index, range) in offsets.windows(2).enumerate() {
+        let intervals = (range[0] as usize..range[1] as usize).map(|interval| OutageInterval {
+            start : starts[interval] as u32,
+            end : ends[interval] as u32
+        }).collect();
+        traffic_lights[index].get_mut().outages = OutageInterval::normalize(intervals);
+    }
+    associate_traffic_lights_to_nodes();
+    invalidate_customization();
 }
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_buildSolver<'l>(_env: JNIEnv<'l>, _class: JClass<'l>) -> jint {
-    add_solver(Solver::new(get_nodes().get_slice(), 0, 0, 100_000_000, SearchMethod::FASTEST)) as jint
+    let mut solver = Solver::new(get_nodes().get_slice(), 0, 0, 100_000_000, SearchMethod::FASTEST);
+    solver.set_reverse_graph(get_reverse_graph());
+    solver.set_network_bounds(get_network_bounds());
+    if let Some(hierarchy) = get_contraction_hierarchy() {
+        solver.set_contraction_hierarchy(hierarchy);
+    }
+    if let Some(hierarchy) = get_customizable_hierarchy() {
+        solver.set_customizable_hierarchy(hierarchy);
+    }
+    if let Some(landmarks) = get_landmarks() {
+        solver.set_landmarks(landmarks);
+    }
+    if let Some(turn_graph) = get_turn_graph() {
+        solver.set_turn_graph(turn_graph);
+    }
+    add_solver(solver) as jint
 }
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_setSearchMethod<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, index : jint, search_method: jint) {
-    get_solver(index as usize).search_method = match search_method {
-        0 => SearchMethod::FASTEST,
-        1 => SearchMethod::SHORTEST,
-        _ => SearchMethod::AVOID
+    get_solver(index as usize).set_search_method(SearchMethod::from_id(search_method))
+}
+
+/// Like `setSearchMethod`, but weighs connections with a custom cost model. Leaving a node near or at a traffic light
+/// whose lights are out takes `near_penalty` or `at_penalty` times as long again, every traffic light intersection
+/// costs `intersection_delay` seconds, and every connection is driven at `fixed_speed` km/h, or at its own speed limit
+/// when `fixed_speed` is 0. Contraction hierarchies and landmarks built for another model are not used. Negative or
+/// non-finite values throw an `IllegalArgumentException` and leave the solver as it was.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_setCustomSearchMethod<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>, index : jint, search_method: jint,
+                                                                                           near_penalty : jdouble, at_penalty : jdouble,
+                                                                                           intersection_delay : jdouble, fixed_speed : jdouble) {
+    let speed = if fixed_speed == 0.0 { SpeedSource::Connection } else { SpeedSource::Fixed(fixed_speed as Cost) };
+    let cost_model = match CostModel::new(near_penalty as Cost, at_penalty as Cost, intersection_delay as Cost, speed) {
+        Ok(cost_model) => cost_model,
+        Err(error) => {
+            env.throw_new("java/lang/IllegalArgumentException", error).expect("Unable to throw exception");
+            return;
+        }
+    };
+    let solver = get_solver(index as usize);
+    solver.search_method = SearchMethod::from_id(search_method);
+    solver.cost_model = cost_model;
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_setUtcOffset<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, index : jint, hours: jdouble) {
+    get_solver(index as usize).utc_offset = hours as Cost
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_setSearchAlgorithm<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, index : jint, search_algorithm: jint) {
+    get_solver(index as usize).search_algorithm = match search_algorithm {
+        0 => SearchAlgorithm::DIJKSTRA,
+        1 => SearchAlgorithm::ASTAR,
+        2 => SearchAlgorithm::BIDIRECTIONAL,
+        3 => SearchAlgorithm::CONTRACTION,
+        4 => SearchAlgorithm::CUSTOMIZABLE,
+        5 => SearchAlgorithm::LANDMARKS,
+        _ => SearchAlgorithm::EDGES
     }
 }
 
 #[no_mangle]
-pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findPath<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
-                                                                              index: jint,
-                                                                              source_x : jdouble, source_y : jdouble,
-                                                                              destination_x : jdouble, destination_y : jdouble) -> jobject {
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_getSettledNodes<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, index : jint) -> jint {
+    get_solver(index as usize).get_settled_nodes() as jint
+}
+
+/// Finds the nodes closest to the source and destination at the same time.
+fn find_end_nodes(source_x : jdouble, source_y : jdouble, destination_x : jdouble, destination_y : jdouble) -> (Option<Index>, Option<Index>) {
     let start_pos = Simd::from_array([source_x as Pos, source_y as Pos]);
     let end_pos = Simd::from_array([destination_x as Pos, destination_y as Pos]);
     let closest_to_start = spawn(move || {get_closest_node(&start_pos)});
     let closest_to_end = spawn(move || {get_closest_node(&end_pos)});
+    (closest_to_start.join().expect("Unable to find closest start"), closest_to_end.join().expect("Unable to find closest end"))
+}
+
+/// Constructor of `JNISolver$Path`, taking the node indices, cost, distance and `JNISolver$PathSegment`s of a path.
+const PATH_SIGNATURE : &str = "([IDD[Lio/github/easterngamer/jni/JNISolver$PathSegment;)V";
+
+fn new_segment_array<'l>(env: &mut JNIEnv<'l>, segments: &[PathSegment]) -> JObjectArray<'l> {
+    let segment_class = env.find_class("io/github/easterngamer/jni/JNISolver$PathSegment").unwrap();
+    let segment_init = env.get_method_id(&segment_class, "<init>", "(IIDDDD)V").unwrap();
+    let segment_array = env.new_object_array(segments.len() as jsize, &segment_class, JObject::null()).expect("Unable to create segment array");
+    for (segment_index, segment) in segments.iter().enumerate() {
+        let arguments = [
+            JValue::from(segment.from as jint).as_jni(),
+            JValue::from(segment.to as jint).as_jni(),
+            JValue::from(segment.distance as f64).as_jni(),
+            JValue::from(segment.base_time as f64).as_jni(),
+            JValue::from(segment.penalty as f64).as_jni(),
+            JValue::from(segment.arrival as f64).as_jni()
+        ];
+        let segment = unsafe { env.new_object_unchecked(&segment_class, segment_init, &arguments).expect("Unable to create object") };
+        env.set_object_array_element(&segment_array, segment_index as jsize, segment).expect("Unable to store segment");
+    }
+    segment_array
+}
+
+fn new_route<'l>(env: &mut JNIEnv<'l>, route: Option<&Route>) -> JObject<'l> {
+    let (path, time, distance, segments, leg_ends) = match route {
+        Some(route) => (route.path.iter().map(|node| *node as jint).collect(), route.time, route.distance, &route.segments[..], route.leg_ends.iter().map(|end| *end as jint).collect()),
+        None => (Vec::new(), 0.0, 0.0, &[][..], Vec::new())
+    };
+    let route_class = env.find_class("io/github/easterngamer/jni/JNISolver$Route").unwrap();
+    let init_method = env.get_method_id(&route_class, "<init>", "([IDD[Lio/github/easterngamer/jni/JNISolver$PathSegment;[I)V").unwrap();
+    let path_array = env.new_int_array(path.len() as jsize).unwrap();
+    env.set_int_array_region(&path_array, 0, &path).expect("Unable to fill route path");
+    let segment_array = new_segment_array(env, segments);
+    let leg_array = env.new_int_array(leg_ends.len() as jsize).unwrap();
+    env.set_int_array_region(&leg_array, 0, &leg_ends).expect("Unable to fill route legs");
+    let arguments = [
+        JValue::from(&path_array).as_jni(),
+        JValue::from(time as f64).as_jni(),
+        JValue::from(distance as f64).as_jni(),
+        JValue::from(&segment_array).as_jni(),
+        JValue::from(&leg_array).as_jni()
+    ];
+    unsafe { env.new_object_unchecked(route_class, init_method, &arguments).expect("Unable to create object") }
+}
+
+fn new_path<'l>(env: &mut JNIEnv<'l>, path_class: &JClass<'l>, init_method: JMethodID, path: &[Index], cost: Cost, distance: Cost, segments: &[PathSegment]) -> JObject<'l> {
+    let indices: Vec<jint> = path
+        .iter()
+        .map(|x| *x as jint)
+        .collect();
+    let indexes = &env.new_int_array(indices.len() as jsize).unwrap();
+    env.set_int_array_region(indexes, 0, indices.as_slice()).expect("TODO: panic message");
+    let segment_array = new_segment_array(env, segments);
+    let array = JValue::from(indexes).as_jni();
+    let cost = JValue::from(cost as f64).as_jni();
+    let distance = JValue::from(distance as f64).as_jni();
+    let segments = JValue::from(&segment_array).as_jni();
+    unsafe { env.new_object_unchecked(path_class, init_method, &[array, cost, distance, segments]).expect("Unable to create object") }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findPath<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                              index: jint,
+                                                                              source_x : jdouble, source_y : jdouble,
+                                                                              destination_x : jdouble, destination_y : jdouble,
+                                                                              departure_time : jlong) -> jobject {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let end_nodes = find_end_nodes(source_x, source_y, destination_x, destination_y);
     let path_class = env.find_class("io/github/easterngamer/jni/JNISolver$Path").unwrap();
-    let init_method = env.get_method_id(&path_class, "<init>", "([IDD)V").unwrap();
+    let init_method = env.get_method_id(&path_class, "<init>", PATH_SIGNATURE).unwrap();
     let solver = get_solver(index as usize);
     
-    match (closest_to_start.join().expect("Unable to find closest start"), closest_to_end.join().expect("Unable to find closest end")) {
+    match end_nodes {
         (Some(start), Some(end)) => {
-            solver.update_search(start, end);
+            solver.update_search(start, end, departure_time);
             solver.update_search_speed(100_000_000);
             while !solver.fully_searched() {
                 solver.compute();
             }
             if let Some((path_data, cost, distance)) = solver.get_path_as_indices().as_ref() {
-                let indices: Vec<jint> = path_data
-                    .iter()
-                    .map(|x| *x as jint)
-                    .collect();
-                let indexes = &env.new_int_array(indices.len() as jsize).unwrap();
-                env.set_int_array_region(indexes, 0, indices.as_slice()).expect("TODO: panic message");
-                let array = JValue::from(indexes).as_jni();
-                let cost = JValue::from(*cost as f64).as_jni();
-                let distance = JValue::from(*distance as f64).as_jni();
-
-                unsafe { env.new_object_unchecked(path_class, init_method, &[array, cost, distance]).expect("Unable to create object").as_raw() }
+                new_path(&mut env, &path_class, init_method, path_data, *cost, *distance, solver.get_path_segments()).as_raw()
             } else {
-                let array = JValue::from(&env.new_int_array(0 as jsize).unwrap()).as_jni();
-                let zero = JValue::from(0.0f64).as_jni();
-                unsafe { env.new_object_unchecked(path_class, init_method, &[array, zero, zero]).expect("Unable to create object").as_raw() }
+                new_path(&mut env, &path_class, init_method, &[], 0.0, 0.0, &[]).as_raw()
             }
         }
-        _ => {
-            let array = JValue::from(&env.new_int_array(0 as jsize).unwrap()).as_jni();
-            let zero = JValue::from(0.0f64).as_jni();
-            unsafe { env.new_object_unchecked(path_class, init_method, &[array, zero, zero]).expect("Unable to create object").as_raw() }
+        _ => new_path(&mut env, &path_class, init_method, &[], 0.0, 0.0, &[]).as_raw()
+    }
+}
+
+/// Route through the given points in order, departing from the first at `departure_time` in milliseconds since the
+/// Unix epoch, as a `JNISolver$Route`. Unlike `JNISolver$Path` its node indices run from the first point to the last,
+/// and `legEnds` holds the position in them of every point after the first. Everything is empty when a point has no
+/// nearby node or a leg has no path.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findRoute<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                               index: jint, xs : JDoubleArray<'l>, ys : JDoubleArray<'l>,
+                                                                               departure_time : jlong) -> jobject {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let size = env.get_array_length(&xs).expect("Failed to read x coordinates") as usize;
+    let mut x_positions = new_slice(0f64, size);
+    let mut y_positions = new_slice(0f64, size);
+    env.get_double_array_region(&xs, 0, &mut x_positions).expect("Failed to read x coordinates");
+    env.get_double_array_region(&ys, 0, &mut y_positions).expect("Failed to read y coordinates");
+    let waypoints: Option<Vec<Index>> = x_positions.par_iter().zip(y_positions.par_iter())
+        .map(|(x, y)| get_closest_node(&Simd::from_array([*x as Pos, *y as Pos])))
+        .collect();
+    let solver = get_solver(index as usize);
+    solver.update_search_speed(100_000_000);
+    let route = waypoints.and_then(|waypoints| Route::new(solver, &waypoints, departure_time));
+    new_route(&mut env, route.as_ref()).as_raw()
+}
+
+/// Best order to visit the given points in, as a `JNISolver$StopOrder` of the positions of the points in visiting order
+/// and the `JNISolver$Route` through them in that order, departing at `departure_time` in milliseconds since the Unix
+/// epoch. The points at `start` and `end` are visited first and last, unless they are -1. Travel times between the
+/// points come from the search method and cost model of solver `index`. Both are empty when a point has no nearby node.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_optimizeStops<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                   index: jint, xs : JDoubleArray<'l>, ys : JDoubleArray<'l>,
+                                                                                   start : jint, end : jint, departure_time : jlong) -> jobject {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let size = env.get_array_length(&xs).expect("Failed to read x coordinates") as usize;
+    let mut x_positions = new_slice(0f64, size);
+    let mut y_positions = new_slice(0f64, size);
+    env.get_double_array_region(&xs, 0, &mut x_positions).expect("Failed to read x coordinates");
+    env.get_double_array_region(&ys, 0, &mut y_positions).expect("Failed to read y coordinates");
+    let stops: Option<Vec<Index>> = x_positions.par_iter().zip(y_positions.par_iter())
+        .map(|(x, y)| get_closest_node(&Simd::from_array([*x as Pos, *y as Pos])))
+        .collect();
+    let depot = |position : jint| (position >= 0 && (position as usize) < size).then_some(position as usize);
+    let solver = get_solver(index as usize);
+    solver.update_search_speed(100_000_000);
+    let (order, route) = match stops {
+        Some(stops) => {
+            let stop_order = StopOrder::new(&stops, depot(start), depot(end), solver, departure_time);
+            let waypoints: Vec<Index> = stop_order.order.iter().map(|position| stops[*position]).collect();
+            let order: Vec<jint> = stop_order.order.iter().map(|position| *position as jint).collect();
+            (order, Route::new(solver, &waypoints, departure_time))
+        }
+        None => (Vec::new(), None)
+    };
+    let order_array = env.new_int_array(order.len() as jsize).unwrap();
+    env.set_int_array_region(&order_array, 0, &order).expect("Unable to fill stop order");
+    let route = new_route(&mut env, route.as_ref());
+    let stop_order_class = env.find_class("io/github/easterngamer/jni/JNISolver$StopOrder").unwrap();
+    let init_method = env.get_method_id(&stop_order_class, "<init>", "([ILio/github/easterngamer/jni/JNISolver$Route;)V").unwrap();
+    let arguments = [JValue::from(&order_array).as_jni(), JValue::from(&route).as_jni()];
+    unsafe { env.new_object_unchecked(stop_order_class, init_method, &arguments).expect("Unable to create object").as_raw() }
+}
+
+/// Turn-by-turn instructions for the path solver `index` found last, as `JNISolver$Instruction`s of a maneuver id, the
+/// distance since the previous instruction, the node, the id of the traffic light there or -1, and whether it will be
+/// out on arrival. Empty when the solver has no path.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_getInstructions<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>, index: jint) -> jobjectArray {
+    let instructions = describe_path(get_solver(index as usize), try_get_traffic_light_tree());
+    let instruction_class = env.find_class("io/github/easterngamer/jni/JNISolver$Instruction").unwrap();
+    let init_method = env.get_method_id(&instruction_class, "<init>", "(IDIIZ)V").unwrap();
+    let array = env.new_object_array(instructions.len() as jsize, &instruction_class, JObject::null()).expect("Unable to create instruction array");
+    for (instruction_index, instruction) in instructions.iter().enumerate() {
+        let arguments = [
+            JValue::from(instruction.maneuver.id()).as_jni(),
+            JValue::from(instruction.distance as f64).as_jni(),
+            JValue::from(instruction.node as jint).as_jni(),
+            JValue::from(instruction.traffic_light.map_or(-1, |id| id as jint)).as_jni(),
+            JValue::from(instruction.warning).as_jni()
+        ];
+        let instruction = unsafe { env.new_object_unchecked(&instruction_class, init_method, &arguments).expect("Unable to create object") };
+        env.set_object_array_element(&array, instruction_index as jsize, instruction).expect("Unable to store instruction");
+    }
+    array.into_raw()
+}
+
+/// Up to `count` paths from the source to the destination, the best first, departing at `departure_time` in
+/// milliseconds since the Unix epoch. Each shares at most `max_overlap` of its distance with the paths before it.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findAlternativePaths<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                          index: jint,
+                                                                                          source_x : jdouble, source_y : jdouble,
+                                                                                          destination_x : jdouble, destination_y : jdouble,
+                                                                                          count : jint, max_overlap : jdouble,
+                                                                                          departure_time : jlong) -> jobjectArray {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let end_nodes = find_end_nodes(source_x, source_y, destination_x, destination_y);
+    let path_class = env.find_class("io/github/easterngamer/jni/JNISolver$Path").unwrap();
+    let init_method = env.get_method_id(&path_class, "<init>", PATH_SIGNATURE).unwrap();
+    let solver = get_solver(index as usize);
+    let routes = match end_nodes {
+        (Some(start), Some(end)) => {
+            solver.update_search(start, end, departure_time);
+            solver.find_alternatives(count as usize, max_overlap as Cost)
         }
+        _ => Vec::new()
+    };
+    let paths = env.new_object_array(routes.len() as jsize, &path_class, JObject::null()).expect("Unable to create path array");
+    for (route_index, (path, cost, distance)) in routes.iter().enumerate() {
+        let mut forwards = path.to_vec();
+        forwards.reverse();
+        let segments = solver.path_segments(&forwards, solver.get_departure_time());
+        let path = new_path(&mut env, &path_class, init_method, path, *cost, *distance, &segments);
+        env.set_object_array_element(&paths, route_index as jsize, path).expect("Unable to store path");
     }
-    
-    
-}
\ No newline at end of file
+    paths.into_raw()
+}
+
+/// Best moment between `earliest` and `latest`, both in milliseconds since the Unix epoch, to leave for the destination,
+/// to the minute, found on the travel time profile between them. Returns the departure in milliseconds since the Unix
+/// epoch, or -1 when there is no path.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findBestDeparture<'l>(_env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                       index: jint,
+                                                                                       source_x : jdouble, source_y : jdouble,
+                                                                                       destination_x : jdouble, destination_y : jdouble,
+                                                                                       earliest : jlong, latest : jlong) -> jlong {
+    let earliest = DateTime::from_timestamp_millis(earliest).expect("Earliest departure out of range");
+    let latest = DateTime::from_timestamp_millis(latest).expect("Latest departure out of range");
+    let solver = get_solver(index as usize);
+    match find_end_nodes(source_x, source_y, destination_x, destination_y) {
+        (Some(start), Some(end)) => {
+            solver.update_search(start, end, earliest);
+            solver.update_search_speed(100_000_000);
+            solver.optimal_departure(earliest, latest).map(|(departure, _)| departure.timestamp_millis()).unwrap_or(-1)
+        }
+        _ => -1
+    }
+}
+
+/// Travel time from the source to the destination for every departure between `earliest` and `latest`, both in
+/// milliseconds since the Unix epoch, as a `JNISolver$Profile` of parallel arrays: departures in minutes since the Unix
+/// epoch and travel times in minutes. Travel times are linear between consecutive departures. Departures from which
+/// the destination cannot be reached are left out, so both arrays are empty when there is no path at all.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_computeProfile<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                    index: jint,
+                                                                                    source_x : jdouble, source_y : jdouble,
+                                                                                    destination_x : jdouble, destination_y : jdouble,
+                                                                                    earliest : jlong, latest : jlong) -> jobject {
+    let profile_class = env.find_class("io/github/easterngamer/jni/JNISolver$Profile").unwrap();
+    let init_method = env.get_method_id(&profile_class, "<init>", "([I[D)V").unwrap();
+    let (departures, durations) = match find_profile(index, source_x, source_y, destination_x, destination_y, earliest, latest) {
+        Some(profile) => {
+            let departures: Vec<jint> = profile.departures.iter().map(|minute| *minute as jint).collect();
+            let durations: Vec<f64> = profile.durations.iter().map(|time| *time as f64 * 60.0).collect();
+            (departures, durations)
+        }
+        None => (Vec::new(), Vec::new())
+    };
+    let departure_array = env.new_int_array(departures.len() as jsize).unwrap();
+    env.set_int_array_region(&departure_array, 0, &departures).expect("Unable to fill profile departures");
+    let duration_array = env.new_double_array(durations.len() as jsize).unwrap();
+    env.set_double_array_region(&duration_array, 0, &durations).expect("Unable to fill profile durations");
+    let arguments = [JValue::from(&departure_array).as_jni(), JValue::from(&duration_array).as_jni()];
+    unsafe { env.new_object_unchecked(profile_class, init_method, &arguments).expect("Unable to create object").as_raw() }
+}
+
+/// Latest moment, no earlier than `earliest`, to leave for the destination and still arrive by `arrive_by`, both in
+/// milliseconds since the Unix epoch. Returns the departure in milliseconds since the Unix epoch, or -1 when even leaving
+/// at `earliest` arrives too late.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_findLatestDeparture<'l>(_env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                         index: jint,
+                                                                                         source_x : jdouble, source_y : jdouble,
+                                                                                         destination_x : jdouble, destination_y : jdouble,
+                                                                                         earliest : jlong, arrive_by : jlong) -> jlong {
+    find_profile(index, source_x, source_y, destination_x, destination_y, earliest, arrive_by)
+        .and_then(|profile| profile.latest_departure(arrive_by as f64 / 60_000.0))
+        .map(|minute| (minute * 60_000.0) as jlong)
+        .unwrap_or(-1)
+}
+
+fn find_profile(index : jint, source_x : jdouble, source_y : jdouble, destination_x : jdouble, destination_y : jdouble, earliest : jlong, latest : jlong) -> Option<Profile> {
+    let earliest = DateTime::from_timestamp_millis(earliest).expect("Earliest departure out of range");
+    let latest = DateTime::from_timestamp_millis(latest).expect("Latest departure out of range");
+    let solver = get_solver(index as usize);
+    match find_end_nodes(source_x, source_y, destination_x, destination_y) {
+        (Some(start), Some(end)) => {
+            solver.update_search(start, end, earliest);
+            solver.update_search_speed(100_000_000);
+            Some(Profile::new(solver, start, end, earliest, latest))
+        }
+        _ => None
+    }
+}
+
+/// Travel time in hours between every pair of the given points, flattened row by row so that the time from point `i`
+/// to point `j` is at `i * xs.length + j`. Pairs that cannot be reached, or points without a nearby node, are infinite.
+/// Every trip departs at `departure_time` in milliseconds since the Unix epoch and is weighed with the search method,
+/// cost model and utc offset of solver `index`.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_computeMatrix<'l>(env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                   index: jint, xs : JDoubleArray<'l>, ys : JDoubleArray<'l>,
+                                                                                   departure_time : jlong) -> jdoubleArray {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let size = env.get_array_length(&xs).expect("Failed to read x coordinates") as usize;
+    let mut x_positions = new_slice(0f64, size);
+    let mut y_positions = new_slice(0f64, size);
+    env.get_double_array_region(&xs, 0, &mut x_positions).expect("Failed to read x coordinates");
+    env.get_double_array_region(&ys, 0, &mut y_positions).expect("Failed to read y coordinates");
+    let closest: Vec<_> = x_positions.par_iter().zip(y_positions.par_iter())
+        .map(|(x, y)| get_closest_node(&Simd::from_array([*x as Pos, *y as Pos])))
+        .collect();
+    let found: Vec<_> = closest.iter().flatten().copied().collect();
+    let solver = get_solver(index as usize);
+    let costs = compute_matrix(get_nodes().get_slice(), &found, &found, solver.search_method, solver.cost_model, departure_time, solver.utc_offset);
+
+    let mut matrix = new_slice(f64::INFINITY, size * size);
+    let found_positions: Vec<usize> = closest.iter().enumerate().filter(|(_, node)| node.is_some()).map(|(index, _)| index).collect();
+    for (row, source) in found_positions.iter().enumerate() {
+        for (column, target) in found_positions.iter().enumerate() {
+            let cost = costs[row * found.len() + column];
+            if cost != Cost::MAX {
+                matrix[source * size + target] = cost as f64;
+            }
+        }
+    }
+    let array = env.new_double_array((size * size) as jsize).expect("Unable to create matrix");
+    env.set_double_array_region(&array, 0, &matrix).expect("Unable to fill matrix");
+    array.into_raw()
+}
+
+/// Nodes reachable from the node closest to (`x`, `y`) within `minutes`, departing at `departure_time` in milliseconds
+/// since the Unix epoch, as a `JNISolver$Isochrone`. Solver `index` only lends its search method, cost model and utc
+/// offset, the search runs on a solver of its own so its path is kept for `getInstructions`.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNISolver_computeIsochrone<'l>(mut env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                      index: jint,
+                                                                                      x : jdouble, y : jdouble,
+                                                                                      minutes : jdouble,
+                                                                                      departure_time : jlong) -> jobject {
+    let departure_time = DateTime::from_timestamp_millis(departure_time).expect("Departure time out of range");
+    let isochrone_class = env.find_class("io/github/easterngamer/jni/JNISolver$Isochrone").unwrap();
+    let init_method = env.get_method_id(&isochrone_class, "<init>", "([I[D[D)V").unwrap();
+    let (nodes, x_points, y_points) = match get_closest_node(&Simd::from_array([x as Pos, y as Pos])) {
+        Some(start) => {
+            // The search cutoff is in seconds, the unit of the solver's CONVERSION_FACTOR.
+            let solver = get_solver(index as usize);
+            let isochrone = compute_isochrone(get_nodes().get_slice(), start, (minutes * 60.0) as u32, solver.search_method,
+                                              solver.cost_model, departure_time, solver.utc_offset);
+            let nodes: Vec<jint> = isochrone.nodes.iter().map(|node| *node as jint).collect();
+            let x_points: Vec<f64> = isochrone.polygon.x_points.iter().map(|x| *x as f64).collect();
+            let y_points: Vec<f64> = isochrone.polygon.y_points.iter().map(|y| *y as f64).collect();
+            (nodes, x_points, y_points)
+        }
+        None => (Vec::new(), Vec::new(), Vec::new())
+    };
+    let node_array = env.new_int_array(nodes.len() as jsize).unwrap();
+    env.set_int_array_region(&node_array, 0, &nodes).expect("Unable to fill isochrone nodes");
+    let x_array = env.new_double_array(x_points.len() as jsize).unwrap();
+    env.set_double_array_region(&x_array, 0, &x_points).expect("Unable to fill isochrone polygon");
+    let y_array = env.new_double_array(y_points.len() as jsize).unwrap();
+    env.set_double_array_region(&y_array, 0, &y_points).expect("Unable to fill isochrone polygon");
+    let arguments = [JValue::from(&node_array).as_jni(), JValue::from(&x_array).as_jni(), JValue::from(&y_array).as_jni()];
+    unsafe { env.new_object_unchecked(isochrone_class, init_method, &arguments).expect("Unable to create object").as_raw() }
+}
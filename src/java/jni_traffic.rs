@@ -1,4 +1,5 @@
 use std::simd::Simd;
+use std::simd::prelude::SimdFloat;
 use std::time::Instant;
 
 use jni::objects::{AsJArrayRaw, JByteArray, JClass};
@@ -6,25 +7,47 @@ use jni::signature::Primitive::Void;
 use jni::signature::ReturnType;
 use jni::sys::{jboolean, jdouble, jint, jintArray, jsize, jvalue};
 use jni::JNIEnv;
-use rayon::prelude::ParallelSlice;
 
 use crate::loader::load_from_bytes;
+use crate::objects::load_shedding::LoadSheddingSchedule;
 use crate::objects::boundary::Boundary;
-use crate::traits::Positional;
-use crate::types::Pos;
-use crate::{add_suburbs, add_traffic_lights, build_traffic_light_tree, compute, distance, get_suburbs, get_traffic_light_tree, get_traffic_lights};
+use crate::objects::traffic_light::TrafficLight;
+use crate::objects::util::super_cell::SuperCell;
+use crate::traits::ByteConvertable;
+use crate::types::{Cost, Pos};
+use crate::{add_suburbs, add_traffic_lights, assign_suburbs, build_suburb_tree, build_traffic_light_tree, compute, find_suburb, get_suburb_tree, get_traffic_light_tree, get_traffic_light_tree_mut, get_traffic_lights, set_load_shedding_schedule, set_load_shedding_stage, set_load_shedding_utc_offset};
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_sendTrafficLights<'l>(env: JNIEnv<'l>, _class: JClass<'l>, data : JByteArray<'l>) {
     let bytes = env.convert_byte_array(&data).expect("Failed to load byte array for traffic lights");
     add_traffic_lights(load_from_bytes(bytes.as_slice()));
-    build_traffic_light_tree();
+    let report = build_traffic_light_tree();
+    if report.dropped > 0 {
+        println!("Rust Binding - Left {} traffic lights without a valid position out of the tree", report.dropped);
+    }
 }
 
 #[no_mangle]
 pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_sendSuburbs<'l> (env: JNIEnv<'l>, _class: JClass<'l>, data : JByteArray<'l>) {
     let bytes = env.convert_byte_array(&data).expect("Failed to load byte array for traffic lights");
-    add_suburbs(load_from_bytes(bytes.as_slice()))
+    add_suburbs(load_from_bytes(bytes.as_slice()));
+    build_suburb_tree();
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_sendLoadSheddingSchedule<'l> (env: JNIEnv<'l>, _class: JClass<'l>, data : JByteArray<'l>) {
+    let bytes = env.convert_byte_array(&data).expect("Failed to load byte array for load shedding schedule");
+    set_load_shedding_schedule(LoadSheddingSchedule::from_bytes(bytes.as_slice()));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_setLoadSheddingStage<'l> (_env: JNIEnv<'l>, _class: JClass<'l>, stage : jint) {
+    set_load_shedding_stage(stage as u8);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_setLoadSheddingUtcOffset<'l> (_env: JNIEnv<'l>, _class: JClass<'l>, hours : jdouble) {
+    set_load_shedding_utc_offset(hours as Cost);
 }
 
 #[no_mangle]
@@ -33,8 +56,7 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getSuburbsInBo
                                                                                                  max_y : jdouble, min_y : jdouble,
                                                                                                  limit : jint, debug : jboolean) -> jintArray {
     let start_time = Instant::now();
-    let result = get_suburbs();
-    let geometries = result.as_slice();
+    let result = get_suburb_tree();
     let boundary = Boundary {
         corner_max: Simd::from_array([max_x as Pos, max_y as Pos]),
         corner_min : Simd::from_array([min_x as Pos, min_y as Pos])
@@ -42,13 +64,10 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getSuburbsInBo
     let time_delta_init = (start_time.elapsed().as_nanos() as f64)/1e6;
 
     let start_filter_time = Instant::now();
-    let limit= limit as usize;
-    let mut ids = Vec::with_capacity(limit);
-    for geometry in geometries {
-        if boundary.does_overlap(&geometry.boundary) && ids.len() <= limit {
-            ids.push(geometry.id as jint);
-        }
-    }
+    let ids : Vec<jint> = result.query_boundary(boundary)
+        .take(limit as usize)
+        .map(|geometry| geometry.id as jint)
+        .collect();
     let time_delta_filter = (start_filter_time.elapsed().as_nanos() as f64)/1e6;
 
     let start_copy_time = Instant::now();
@@ -71,8 +90,7 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getTrafficLigh
                                                                                                  max_y : jdouble, min_y : jdouble,
                                                                                                  limit : jint, debug : jboolean) -> jintArray {
     let start_time = Instant::now();
-    let result = get_traffic_lights();
-    let traffic_lights = result.as_slice();
+    let result = get_traffic_light_tree();
     let boundary = Boundary {
         corner_max : Simd::from_array([max_x as Pos, max_y as Pos]),
         corner_min: Simd::from_array([min_x as Pos, min_y as Pos])
@@ -80,13 +98,10 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getTrafficLigh
     let time_delta_init = (start_time.elapsed().as_nanos() as f64)/1e6;
 
     let start_filter_time = Instant::now();
-    let limit= limit as usize;
-    let mut ids = Vec::with_capacity(limit);
-    for traffic_light in traffic_lights {
-        if boundary.contains(&traffic_light.position) && ids.len() <= limit {
-            ids.push(traffic_light.id as jint);
-        }
-    }
+    let ids : Vec<jint> = result.query_boundary(boundary)
+        .take(limit as usize)
+        .map(|traffic_light| traffic_light.get().id as jint)
+        .collect();
     let time_delta_filter = (start_filter_time.elapsed().as_nanos() as f64)/1e6;
 
     let start_copy_time = Instant::now();
@@ -103,38 +118,94 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getTrafficLigh
     indexes.as_jarray_raw()
 }
 
+/// Ids of up to `limit` traffic lights at most `metres` away from the given position.
 #[no_mangle]
-pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getNearestTrafficLight<'l>(_env: JNIEnv<'l>, _class: JClass<'l>,
-                                                                                               x : jdouble, y : jdouble,
-                                                                                               debug : jboolean) -> jint {
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getTrafficLightsInRadius<'l>(env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                                 x : jdouble, y : jdouble, metres : jdouble,
+                                                                                                 limit : jint, debug : jboolean) -> jintArray {
     let start_time = Instant::now();
     let result = get_traffic_light_tree();
     let position = Simd::from_array([x as Pos, y as Pos]);
     let time_delta_init = (start_time.elapsed().as_nanos() as f64)/1e6;
 
     let start_filter_time = Instant::now();
-    let optional_nearest_list = result.find_data(&position);
+    let ids : Vec<jint> = result.query_radius(position, metres as Pos)
+        .take(limit as usize)
+        .map(|traffic_light| traffic_light.get().id as jint)
+        .collect();
     let time_delta_filter = (start_filter_time.elapsed().as_nanos() as f64)/1e6;
 
-    let start_nearest_time = Instant::now();
-    let mut nearest = &get_traffic_lights().get_slice()[0];
-    let mut nearest_distance = Pos::MAX;
-    if let Some(nearest_list) = optional_nearest_list {
-        for item in nearest_list {
-            let distance = distance(&position, item.position());
-            if distance < nearest_distance {
-                nearest_distance = distance;
-                nearest = item;
-            }
-        }
+    let start_copy_time = Instant::now();
+    let indexes = &env.new_int_array(ids.len() as jsize).unwrap();
+    env.set_int_array_region(indexes, 0, ids.as_slice()).expect("TODO: panic message");
+    let time_delta_copy = (start_copy_time.elapsed().as_nanos() as f64)/1e6;
+
+    if debug == 1u8 {
+        println!("Rust Binding - Initialization Time: {time_delta_init}ms");
+        println!("Rust Binding - Filter Time: {time_delta_filter}ms");
+        println!("Rust Binding - Copy Time: {time_delta_copy}ms");
     }
+
+    indexes.as_jarray_raw()
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getNearestTrafficLight<'l>(_env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                               x : jdouble, y : jdouble,
+                                                                                               debug : jboolean) -> jint {
+    let start_time = Instant::now();
+    let result = get_traffic_light_tree();
+    let position = Simd::from_array([x as Pos, y as Pos]);
+    let time_delta_init = (start_time.elapsed().as_nanos() as f64)/1e6;
+
+    let start_nearest_time = Instant::now();
+    let nearest = result.find_nearest(&position, None);
     let time_delta_nearest = (start_nearest_time.elapsed().as_nanos() as f64)/1e6;
     if debug == 1u8 {
         println!("Rust Binding - Initialization Time: {time_delta_init}ms");
-        println!("Rust Binding - Filter Time: {time_delta_filter}ms");
         println!("Rust Binding - Nearest Time: {time_delta_nearest}ms");
     }
-    nearest.get().id as jint
+    nearest.map_or(-1, |cell| cell.get().id as jint)
+}
+
+fn find_traffic_light(id : jint) -> Option<&'static SuperCell<TrafficLight>> {
+    get_traffic_lights().get_slice().iter().find(|cell| cell.get().id as jint == id)
+}
+
+/// Moves traffic light `id` to the given position and updates the traffic light tree in place, without rebuilding it.
+/// Suburbs and node types are only updated by the next `compute` and load. `false` when there is no such traffic light,
+/// it was taken out of the tree by `removeTrafficLight` or the position is not a finite number, in which case the
+/// traffic light stays where it was.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_moveTrafficLight<'l>(_env: JNIEnv<'l>, _class: JClass<'l>,
+                                                                                        id : jint, x : jdouble, y : jdouble) -> jboolean {
+    let position = Simd::from_array([x as Pos, y as Pos]);
+    if !position.is_finite().all() {
+        return 0;
+    }
+    let Some(cell) = find_traffic_light(id) else {
+        return 0;
+    };
+    let old_position = cell.get().position;
+    cell.get_mut().position = position;
+    let moved = get_traffic_light_tree_mut().move_data(cell, &old_position);
+    if !moved {
+        cell.get_mut().position = old_position;
+    }
+    moved as jboolean
+}
+
+/// Takes traffic light `id` out of the traffic light tree, so that the nearest, bounds and radius lookups no longer
+/// return it. `false` when it was not in the tree.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_removeTrafficLight<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, id : jint) -> jboolean {
+    find_traffic_light(id).is_some_and(|cell| get_traffic_light_tree_mut().remove(cell)) as jboolean
+}
+
+/// Id of the smallest suburb the given position lies inside, or -1 when it is in none.
+#[no_mangle]
+pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_getSuburbAt<'l>(_env: JNIEnv<'l>, _class: JClass<'l>, x : jdouble, y : jdouble) -> jint {
+    find_suburb(get_suburb_tree(), &Simd::from_array([x as Pos, y as Pos])).map_or(-1, |suburb| suburb.id as jint)
 }
 
 #[no_mangle]
@@ -143,14 +214,14 @@ pub extern "system" fn Java_io_github_easterngamer_jni_JNITraffic_compute<'l>(mu
     let start_time_pre = Instant::now();
 
     let method_id = env.get_static_method_id(&class, "receiveTrafficLight", "(II)V").expect("Something went wrong getting static method");
-    let temp_geo = get_suburbs();
+    let suburb_tree = get_suburb_tree();
     let temp_traffic = get_traffic_lights();
-    let geometries = temp_geo.as_slice().as_parallel_slice();
     let traffic_lights = temp_traffic.as_slice();
     let time_delta_init = (start_time_pre.elapsed().as_nanos() as f64) / 1e6;
 
     let start_time_map = Instant::now();
-    let results: Vec<(jint, jint)> = compute(geometries, traffic_lights);
+    let results: Vec<(jint, jint)> = compute(suburb_tree, traffic_lights);
+    assign_suburbs(&results);
     let time_delta_map = (start_time_map.elapsed().as_nanos() as f64) / 1e6;
 
     let start_time_push = Instant::now();
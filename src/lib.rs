@@ -2,7 +2,7 @@
 #![feature(duration_millis_float)]
 #![feature(slice_pattern)]
 #![feature(new_uninit)]
-#![feature(maybe_uninit_uninit_array)]
+
 #![feature(maybe_uninit_slice)]
 #![feature(const_for)]
 
@@ -12,22 +12,33 @@ use std::simd::cmp::SimdPartialOrd;
 use std::simd::num::SimdFloat;
 use std::simd::*;
 
+use chrono::{DateTime, Utc};
 use jni::sys::jint;
 use rayon::prelude::*;
 
 use objects::boundary::Boundary;
+use objects::load_shedding::{LoadSheddingSchedule, SCHEDULE_HORIZON_DAYS};
+use objects::pathing::cost_model::CostModel;
+use objects::pathing::contraction_hierarchy::ContractionHierarchy;
+use objects::pathing::customizable_hierarchy::CustomizableHierarchy;
+use objects::pathing::isochrone::{Isochrone, DEFAULT_RESOLUTION};
+use objects::pathing::landmarks::Landmarks;
 use objects::pathing::node::Node;
-use objects::pathing::solver::Solver;
+use objects::pathing::reverse_graph::ReverseGraph;
+use objects::pathing::solver::{NetworkBounds, Solver, SOUTH_AFRICA_UTC_OFFSET};
+use objects::pathing::turn_restriction::TurnRestriction;
+use objects::pathing::turns::{TurnCosts, TurnGraph};
 use objects::suburb::Suburb;
 use objects::traffic_light::TrafficLight;
 use objects::util::parallel_list::ParallelList;
-use objects::util::quad_tree::QuadTree;
+use objects::util::quad_tree::{InsertionReport, QuadTree};
+use objects::util::r_tree::RTree;
 use objects::util::super_cell::SuperCell;
 use traits::Positional;
 
-use crate::objects::pathing::node_type::NodeType;
+use crate::objects::pathing::node_type::{NodeType, SearchMethod};
 use crate::traits::Indexable;
-use crate::types::{Index, Pos};
+use crate::types::{Cost, Index, Pos};
 
 pub mod loader;
 pub mod types;
@@ -36,14 +47,24 @@ pub mod traits;
 pub mod objects;
 pub mod debug_window;
 
-const MULTIPLIER: Simd<Pos, 2> = Simd::from_array([85295.2, 110948.0]);
+pub const MULTIPLIER: Simd<Pos, 2> = Simd::from_array([85295.2, 110948.0]);
 
 pub static mut SOLVERS : Option<ParallelList<Solver>> = None;
 pub static mut SUBURBS: Option<ParallelList<Suburb>> = None;
 pub static mut TRAFFIC_LIGHTS : Option<ParallelList<TrafficLight>> = None;
 pub static mut NODES : Option<ParallelList<Node>> = None;
+pub static mut REVERSE_GRAPH : Option<ReverseGraph> = None;
+pub static mut NETWORK_BOUNDS : Option<NetworkBounds> = None;
+pub static mut CONTRACTION_HIERARCHY : Option<ContractionHierarchy> = None;
+pub static mut CUSTOMIZABLE_HIERARCHY : Option<SuperCell<CustomizableHierarchy>> = None;
+pub static mut LANDMARKS : Option<Landmarks> = None;
+pub static mut ISOCHRONE : Option<Isochrone> = None;
+pub static mut TURN_RESTRICTIONS : Option<ParallelList<TurnRestriction>> = None;
+pub static mut TURN_GRAPH : Option<TurnGraph> = None;
+pub static mut LOAD_SHEDDING_SCHEDULE : Option<LoadSheddingSchedule> = None;
 pub static mut NODE_TREE : Option<QuadTree<SuperCell<Node>>> = None;
 pub static mut TRAFFIC_LIGHT_TREE : Option<QuadTree<SuperCell<TrafficLight>>> = None;
+pub static mut SUBURB_TREE : Option<RTree<Suburb>> = None;
 
 
 fn get_boundary<T : Positional + Sync>(values : &[SuperCell<T>]) -> (Simd<Pos, 2>, Simd<Pos, 2>) {
@@ -62,23 +83,23 @@ fn get_boundary<T : Positional + Sync>(values : &[SuperCell<T>]) -> (Simd<Pos, 2
     (max, min)
 }
 
-pub fn create_tree<T : Positional + Sync>(values : &[SuperCell<T>]) -> QuadTree<SuperCell<T>> {
+pub fn create_tree<T : Positional + Sync>(values : &[SuperCell<T>]) -> (QuadTree<SuperCell<T>>, InsertionReport) {
     let (max, min) = get_boundary(values);
-    let mut tree = QuadTree::new(Boundary {
+    QuadTree::from_items(Boundary {
         corner_max: max,
         corner_min: min,
-    }, 0);
-    for value in values {
-        tree.add_data(value);
-    }
-    tree
+    }, values)
 }
 
 pub fn associate_traffic_lights_to_nodes() {
     get_nodes()
         .get_slice_mut()
         .par_iter_mut()
-        .for_each(|x| {x.get_mut().node_type = NodeType::Normal});
+        .for_each(|x| {
+            let node = x.get_mut();
+            node.node_type = NodeType::Normal;
+            node.outages = Box::new([]);
+        });
     for traffic_light in get_traffic_lights().as_slice() {
         if let Some(data) = get_node_tree().find_data(traffic_light.position()) {
             let d = data.as_slice();
@@ -112,6 +133,38 @@ pub fn get_solver(index : usize) -> &'static mut Solver<'static> {
     }
 }
 #[inline]
+pub fn get_reverse_graph() -> &'static ReverseGraph {
+    unsafe { REVERSE_GRAPH.as_ref().unwrap() }
+}
+#[inline]
+pub fn get_network_bounds() -> NetworkBounds {
+    unsafe { NETWORK_BOUNDS.unwrap() }
+}
+#[inline]
+pub fn get_contraction_hierarchy() -> Option<&'static ContractionHierarchy> {
+    unsafe { CONTRACTION_HIERARCHY.as_ref() }
+}
+#[inline]
+pub fn get_customizable_hierarchy() -> Option<&'static SuperCell<CustomizableHierarchy>> {
+    unsafe { CUSTOMIZABLE_HIERARCHY.as_ref() }
+}
+#[inline]
+pub fn get_landmarks() -> Option<&'static Landmarks> {
+    unsafe { LANDMARKS.as_ref() }
+}
+#[inline]
+pub fn get_turn_graph() -> Option<&'static TurnGraph> {
+    unsafe { TURN_GRAPH.as_ref() }
+}
+#[inline]
+pub fn get_load_shedding_schedule() -> Option<&'static LoadSheddingSchedule> {
+    unsafe { LOAD_SHEDDING_SCHEDULE.as_ref() }
+}
+#[inline]
+pub fn get_isochrone() -> Option<&'static Isochrone> {
+    unsafe { ISOCHRONE.as_ref() }
+}
+#[inline]
 pub fn get_node_tree() -> &'static mut QuadTree<'static, SuperCell<Node>> {
     unsafe { NODE_TREE.as_mut().unwrap() }
 }
@@ -121,6 +174,14 @@ pub fn get_traffic_light_tree() -> &'static QuadTree<'static, SuperCell<TrafficL
     unsafe { TRAFFIC_LIGHT_TREE.as_ref().unwrap() }
 }
 #[inline]
+pub fn get_traffic_light_tree_mut() -> &'static mut QuadTree<'static, SuperCell<TrafficLight>> {
+    unsafe { TRAFFIC_LIGHT_TREE.as_mut().unwrap() }
+}
+#[inline]
+pub fn try_get_traffic_light_tree() -> Option<&'static QuadTree<'static, SuperCell<TrafficLight>>> {
+    unsafe { TRAFFIC_LIGHT_TREE.as_ref() }
+}
+#[inline]
 pub fn add_traffic_lights(traffic_lights: ParallelList<TrafficLight>) {
     unsafe { TRAFFIC_LIGHTS = Some(traffic_lights);}
 }
@@ -135,6 +196,26 @@ pub fn add_suburbs(suburbs : ParallelList<Suburb>) {
     }
 }
 #[inline]
+pub fn get_suburb_tree() -> &'static RTree<'static, Suburb> {
+    unsafe { SUBURB_TREE.as_ref().unwrap() }
+}
+#[inline]
+pub fn build_suburb_tree() {
+    unsafe {
+        SUBURB_TREE = Some(RTree::new(get_suburbs().as_slice()));
+    }
+}
+#[inline]
+pub fn add_turn_restrictions(turn_restrictions : ParallelList<TurnRestriction>) {
+    unsafe { TURN_RESTRICTIONS = Some(turn_restrictions);}
+}
+#[inline]
+pub fn add_isochrone(isochrone : Isochrone) {
+    unsafe {
+        ISOCHRONE = Some(isochrone);
+    }
+}
+#[inline]
 pub fn add_solver(solver: Solver<'static>) -> usize {
     unsafe {
         match SOLVERS.as_mut() {  
@@ -165,32 +246,227 @@ pub fn remove_solver() {
 }
 
 #[inline]
-pub fn build_node_tree() {
+/// Builds the tree of nodes, returning how many went into it and how many were left out.
+pub fn build_node_tree() -> InsertionReport {
+    let (tree, report) = create_tree(get_nodes().get_slice());
     unsafe {
-        NODE_TREE = Some(create_tree(get_nodes().get_slice()));
+        NODE_TREE = Some(tree);
     }
+    report
 }
 
 #[inline]
-pub fn build_traffic_light_tree() {
+pub fn build_reverse_graph() {
     unsafe {
-        TRAFFIC_LIGHT_TREE = Some(create_tree(get_traffic_lights().get_slice()));
+        REVERSE_GRAPH = Some(ReverseGraph::new(get_nodes().get_slice()));
     }
 }
 
-pub fn get_closest_node(position : &Simd<Pos, 2>) -> Option<Index> {
-    let mut closest = None;
-    let mut current_distance = Pos::MAX;
-    if let Some(list) = get_node_tree().find_data(&position) {
-        for cell in list {
-            let cell_distance = distance(cell.position(), &position);
-            if current_distance > cell_distance {
-                current_distance = cell_distance;
-                closest = Some(cell);
+/// Works out the bounds of the loaded graph for the A* heuristic once, and hands them to every solver.
+pub fn build_network_bounds() {
+    let network_bounds = NetworkBounds::new(get_nodes().get_slice());
+    unsafe {
+        NETWORK_BOUNDS = Some(network_bounds);
+        if let Some(solvers) = SOLVERS.as_ref() {
+            for index in 0..solvers.len {
+                solvers.get_mut(index).set_network_bounds(network_bounds);
+            }
+        }
+    }
+}
+
+/// Loads the contraction hierarchy stored next to `nodes_file`, building and saving it first when it is missing or
+/// out of date, and hands it to every solver.
+pub fn build_contraction_hierarchy(nodes_file : &str, search_method : SearchMethod) {
+    let file_location = ContractionHierarchy::file_location(nodes_file);
+    let nodes = get_nodes().get_slice();
+    let hierarchy = match ContractionHierarchy::load(&file_location, nodes, search_method) {
+        Ok(hierarchy) => hierarchy,
+        Err(error) => {
+            println!("Building contraction hierarchy, could not load {}: {error}", file_location.display());
+            let hierarchy = ContractionHierarchy::new(nodes, search_method);
+            if let Err(error) = hierarchy.save(&file_location) {
+                println!("Failed to save contraction hierarchy to {}: {error}", file_location.display());
+            }
+            hierarchy
+        }
+    };
+    unsafe {
+        CONTRACTION_HIERARCHY = Some(hierarchy);
+        if let Some(solvers) = SOLVERS.as_ref() {
+            for index in 0..solvers.len {
+                solvers.get_mut(index).set_contraction_hierarchy(CONTRACTION_HIERARCHY.as_ref().unwrap());
+            }
+        }
+    }
+}
+
+/// Loads the customizable hierarchy topology stored next to `nodes_file`, building and saving it first when it is
+/// missing or out of date, and hands it to every solver, which customizes it for the departure of its searches.
+pub fn build_customizable_hierarchy(nodes_file : &str, search_method : SearchMethod) {
+    let file_location = CustomizableHierarchy::file_location(nodes_file);
+    let nodes = get_nodes().get_slice();
+    let hierarchy = match CustomizableHierarchy::load(&file_location, nodes, search_method) {
+        Ok(hierarchy) => hierarchy,
+        Err(error) => {
+            println!("Building customizable hierarchy, could not load {}: {error}", file_location.display());
+            let hierarchy = CustomizableHierarchy::new(nodes, search_method);
+            if let Err(error) = hierarchy.save(&file_location) {
+                println!("Failed to save customizable hierarchy to {}: {error}", file_location.display());
+            }
+            hierarchy
+        }
+    };
+    unsafe {
+        CUSTOMIZABLE_HIERARCHY = Some(SuperCell::new(hierarchy));
+        if let Some(solvers) = SOLVERS.as_ref() {
+            for index in 0..solvers.len {
+                solvers.get_mut(index).set_customizable_hierarchy(CUSTOMIZABLE_HIERARCHY.as_ref().unwrap());
+            }
+        }
+    }
+}
+
+/// Makes the next customizable search reweigh the hierarchy, if one was built, for the changed node flags or outages.
+/// Only the weights are recomputed, so this is cheap enough to run on every flag update.
+pub fn invalidate_customization() {
+    if let Some(hierarchy) = get_customizable_hierarchy() {
+        hierarchy.get_mut().invalidate();
+    }
+}
+
+/// Loads the landmark tables stored next to `nodes_file`, computing and saving them first when they are missing or
+/// out of date, and hands them to every solver.
+pub fn build_landmarks(nodes_file : &str, count : usize, search_method : SearchMethod) {
+    let file_location = Landmarks::file_location(nodes_file);
+    let nodes = get_nodes().get_slice();
+    let landmarks = match Landmarks::load(&file_location, nodes, count, search_method) {
+        Ok(landmarks) => landmarks,
+        Err(error) => {
+            println!("Computing landmarks, could not load {}: {error}", file_location.display());
+            let start = std::time::Instant::now();
+            let mut solver = Solver::new(nodes, 0, 0, u32::MAX, search_method);
+            solver.set_reverse_graph(get_reverse_graph());
+            let landmarks = Landmarks::new(&mut solver, count);
+            println!("Computed {} landmarks in {}ms", landmarks.landmarks.len(), start.elapsed().as_millis_f64());
+            if let Err(error) = landmarks.save(&file_location) {
+                println!("Failed to save landmarks to {}: {error}", file_location.display());
+            }
+            landmarks
+        }
+    };
+    unsafe {
+        LANDMARKS = Some(landmarks);
+        if let Some(solvers) = SOLVERS.as_ref() {
+            for index in 0..solvers.len {
+                solvers.get_mut(index).set_landmarks(LANDMARKS.as_ref().unwrap());
+            }
+        }
+    }
+}
+
+/// Numbers the connections of the loaded nodes for edge based searches, with the loaded turn restrictions if any,
+/// and hands the result to every solver. Turn costs set before are kept.
+pub fn build_turn_graph() {
+    let restrictions = unsafe { TURN_RESTRICTIONS.as_ref() }.map(|restrictions| restrictions.as_slice()).unwrap_or(&[]);
+    let mut turn_graph = TurnGraph::new(get_nodes().get_slice(), restrictions);
+    unsafe {
+        turn_graph.turn_costs = TURN_GRAPH.as_ref().and_then(|previous| previous.turn_costs);
+        TURN_GRAPH = Some(turn_graph);
+        if let Some(solvers) = SOLVERS.as_ref() {
+            for index in 0..solvers.len {
+                solvers.get_mut(index).set_turn_graph(TURN_GRAPH.as_ref().unwrap());
             }
         }
     }
-    closest.map(|t| {t.get().index})
+}
+
+/// Sets the turn costs charged by edge based searches, or stops charging them with `None`.
+pub fn set_turn_costs(turn_costs : Option<TurnCosts>) {
+    unsafe {
+        if TURN_GRAPH.is_none() {
+            build_turn_graph();
+        }
+        TURN_GRAPH.as_mut().unwrap().turn_costs = turn_costs;
+    }
+}
+
+/// Replaces the load shedding schedule and takes the outages of the traffic lights in its suburbs from it.
+pub fn set_load_shedding_schedule(schedule : LoadSheddingSchedule) {
+    unsafe {
+        LOAD_SHEDDING_SCHEDULE = Some(schedule);
+        if TRAFFIC_LIGHTS.is_some() {
+            assign_load_shedding_blocks();
+        }
+    }
+}
+
+/// Changes the stage of the loaded schedule and recomputes the outages of the traffic lights it covers.
+pub fn set_load_shedding_stage(stage : u8) {
+    unsafe {
+        if let Some(schedule) = LOAD_SHEDDING_SCHEDULE.as_mut() {
+            schedule.stage = stage;
+        }
+        if TRAFFIC_LIGHTS.is_some() {
+            assign_load_shedding_blocks();
+        }
+    }
+}
+
+/// Changes the time zone of the loaded schedule, `utc_offset` hours ahead of UTC, and recomputes the outages of the
+/// traffic lights it covers.
+pub fn set_load_shedding_utc_offset(utc_offset : Cost) {
+    unsafe {
+        if let Some(schedule) = LOAD_SHEDDING_SCHEDULE.as_mut() {
+            schedule.utc_offset = utc_offset;
+        }
+        if TRAFFIC_LIGHTS.is_some() {
+            assign_load_shedding_blocks();
+        }
+    }
+}
+
+/// Records the suburb `compute` found for every traffic light, given in the same order as the traffic lights, and
+/// takes their outages from the schedule.
+pub fn assign_suburbs(suburbs : &[(jint, jint)]) {
+    for (cell, (_, suburb)) in get_traffic_lights().get_slice_mut().iter_mut().zip(suburbs) {
+        cell.get_mut().suburb = *suburb as Index;
+    }
+    assign_load_shedding_blocks();
+}
+
+/// Looks up the block of every traffic light's suburb in the schedule and turns the block's outages over the next
+/// `SCHEDULE_HORIZON_DAYS` into the traffic light's intervals, then passes them on to the nodes around the traffic
+/// lights. Traffic lights in suburbs the schedule does not cover keep the outages they were given directly.
+pub fn assign_load_shedding_blocks() {
+    let schedule = get_load_shedding_schedule();
+    let utc_offset = schedule.map_or(SOUTH_AFRICA_UTC_OFFSET, |schedule| schedule.utc_offset);
+    let today = Solver::local_time(Utc::now(), utc_offset).date();
+    get_traffic_lights().get_slice_mut().par_iter_mut().for_each(|cell| {
+        let traffic_light = cell.get_mut();
+        traffic_light.block = schedule.and_then(|schedule| schedule.block_of(traffic_light.suburb));
+        if let (Some(schedule), Some(block)) = (schedule, traffic_light.block) {
+            traffic_light.outages = schedule.outage_intervals(block, today, SCHEDULE_HORIZON_DAYS);
+        }
+    });
+    if unsafe { NODES.is_some() } {
+        associate_traffic_lights_to_nodes();
+        invalidate_customization();
+    }
+}
+
+#[inline]
+/// Builds the tree of traffic lights, returning how many went into it and how many were left out.
+pub fn build_traffic_light_tree() -> InsertionReport {
+    let (tree, report) = create_tree(get_traffic_lights().get_slice());
+    unsafe {
+        TRAFFIC_LIGHT_TREE = Some(tree);
+    }
+    report
+}
+
+pub fn get_closest_node(position : &Simd<Pos, 2>) -> Option<Index> {
+    get_node_tree().find_nearest(position, None).map(|cell| cell.get().index)
 }
 
 #[inline]
@@ -214,22 +490,78 @@ pub fn new_u8_slice(size: usize) -> Box<[u8]> {
     new_slice(0u8, size)
 }
 
+/// Cost from every source node to every target node of `nodes`, flattened row by row so that the cost from
+/// `sources[row]` to `targets[column]` is at `row * targets.len() + column`, or `Cost::MAX` when there is no path. Runs
+/// one search per source, spread across the rayon workers, each of which reuses a single solver for its share of the
+/// sources. Every search departs at `departure_time` and weighs connections with `cost_model`, with the outages read
+/// in a time zone `utc_offset` hours ahead of UTC.
+pub fn compute_matrix(nodes : &[SuperCell<Node>], sources : &[Index], targets : &[Index], search_method : SearchMethod,
+                      cost_model : CostModel, departure_time : DateTime<Utc>, utc_offset : Cost) -> Box<[Cost]> {
+    let chunk_size = sources.len().div_ceil(rayon::current_num_threads()).max(1);
+    sources.par_chunks(chunk_size).flat_map_iter(|chunk| {
+        let mut solver = Solver::new(nodes, 0, 0, u32::MAX, search_method);
+        solver.cost_model = cost_model;
+        solver.utc_offset = utc_offset;
+        solver.set_departure_time(departure_time);
+        chunk.iter().flat_map(|source| solver.one_to_many(*source, targets).into_vec()).collect::<Vec<Cost>>()
+    }).collect()
+}
+
+/// Everything reachable from `source` within `cutoff` seconds, searched on a solver of its own so the path and costs of
+/// the solvers in `SOLVERS` are left alone.
+pub fn compute_isochrone(nodes : &[SuperCell<Node>], source : Index, cutoff : u32, search_method : SearchMethod,
+                         cost_model : CostModel, departure_time : DateTime<Utc>, utc_offset : Cost) -> Isochrone {
+    let mut solver = Solver::new(nodes, source as usize, source as usize, u32::MAX, search_method);
+    solver.cost_model = cost_model;
+    solver.utc_offset = utc_offset;
+    solver.set_departure_time(departure_time);
+    Isochrone::new(&mut solver, source, cutoff, DEFAULT_RESOLUTION)
+}
+
 #[inline]
-pub fn compute(geometries : &[Suburb], traffic_lights: &[TrafficLight]) -> Vec<(jint, jint)> {
+/// The suburb in `suburb_tree` with the smallest area that `position` lies inside, if any, the lowest id among equals.
+pub fn find_suburb<'life>(suburb_tree : &RTree<'life, Suburb>, position : &Simd<Pos, 2>) -> Option<&'life Suburb> {
+    suburb_tree.query_point(*position)
+        .filter(|suburb| suburb.is_inside(position))
+        .min_by(|a, b| a.area.total_cmp(&b.area).then(a.id.cmp(&b.id)))
+}
+
+pub fn compute(suburb_tree : &RTree<Suburb>, traffic_lights: &[TrafficLight]) -> Vec<(jint, jint)> {
     traffic_lights.par_iter().map(|traffic_light| {
-        let mut suburb : Option<&Suburb> = None;
-        geometries.iter()
-            .filter(|geometry| geometry.is_inside(&traffic_light.position))
-            .for_each(|x| {
-                match suburb {  
-                    Some(other) => {
-                        if other.boundary.area() > x.boundary.area() {
-                            suburb = Some(x)
-                        }
-                    }
-                    None => suburb = Some(x)
-                }
-            });
+        let suburb = find_suburb(suburb_tree, &traffic_light.position);
         (traffic_light.id as jint, suburb.map(|x1| {x1.id}).unwrap_or(0usize as Index) as jint)
     }).collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::load_shedding::OutageInterval;
+    use crate::objects::pathing::test_graph::{grid, same_cost};
+
+    #[test]
+    fn matrix_entries_match_single_searches() {
+        let nodes = grid(4, 4, 1);
+        let departure = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (departure.timestamp() / 60) as u32;
+        let traffic_light = nodes.get_slice()[5].get_mut();
+        traffic_light.node_type = NodeType::AtTrafficLight;
+        traffic_light.outages = Box::new([OutageInterval { start : minute, end : minute + 60 }]);
+        let (sources, targets) = ([0, 5, 16, 15, 9], [3, 16, 10, 0, 5, 15]);
+        let cost_model = SearchMethod::AVOID.cost_model();
+        let matrix = compute_matrix(nodes.get_slice(), &sources, &targets, SearchMethod::AVOID, cost_model, departure, 1.5);
+        assert_eq!(matrix.len(), sources.len() * targets.len());
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::AVOID);
+        solver.utc_offset = 1.5;
+        for (row, source) in sources.iter().enumerate() {
+            for (column, target) in targets.iter().enumerate() {
+                let cost = matrix[row * targets.len() + column];
+                match solver.travel_time(*source, *target, departure) {
+                    Some(expected) => assert!(same_cost(Some(cost), Some(expected)), "{source} to {target}: {cost} != {expected}"),
+                    None => assert_eq!(cost, Cost::MAX, "{source} to {target}")
+                }
+                assert_eq!(cost == Cost::MAX, (*source == 16) != (*target == 16), "{source} to {target}");
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::path::Path;
 use rayon::prelude::*;
@@ -32,6 +32,45 @@ pub fn read_string(vector: &[u8], index: &mut usize, string_len: usize) -> Strin
     String::from_utf8_lossy(x).to_string()
 }
 
+#[inline]
+pub fn write_f64(vector: &mut Vec<u8>, value: f64) {
+    vector.extend_from_slice(&value.to_be_bytes());
+}
+
+#[inline]
+pub fn write_i32(vector: &mut Vec<u8>, value: i32) {
+    vector.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Fails unless `vector` holds another `length` bytes after `index`, so that truncated files are reported instead of
+/// read past their end.
+pub fn ensure_remaining(vector: &[u8], index: usize, length: usize) -> Result<(), String> {
+    match index.checked_add(length) {
+        Some(end) if end <= vector.len() => Ok(()),
+        _ => Err(format!("File ends after {} bytes, expected {length} more at byte {index}", vector.len()))
+    }
+}
+
+/// Writes the header of a file derived from the loaded graph: its format version and the checksum of the graph.
+pub fn write_header(vector: &mut Vec<u8>, version: i32, checksum: u64) {
+    write_i32(vector, version);
+    write_i32(vector, (checksum >> 32) as i32);
+    write_i32(vector, checksum as i32);
+}
+
+/// Reads the header written by `write_header`, returning the graph checksum, or failing if the file was written in
+/// another format version.
+pub fn read_header(vector: &[u8], index: &mut usize, version: i32) -> Result<u64, String> {
+    ensure_remaining(vector, *index, 12)?;
+    let file_version = read_i32(vector, index);
+    if file_version != version {
+        return Err(format!("File has format version {file_version}, expected {version}"));
+    }
+    let high = read_i32(vector, index) as u32 as u64;
+    let low = read_i32(vector, index) as u32 as u64;
+    Ok(high << 32 | low)
+}
+
 #[inline]
 pub fn skip_f64(index: &mut usize) {
     *index += 8;
@@ -48,6 +87,24 @@ pub fn skip_string(index: &mut usize, string_len: usize) {
 }
 
 
+pub fn read_file(file_location : &Path) -> Result<Vec<u8>, String> {
+    match File::open(file_location) {
+        Ok(mut file) => {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+            Ok(data)
+        },
+        Err(e) => Err(e.to_string())
+    }
+}
+
+pub fn write_file(file_location : &Path, data : &[u8]) -> Result<(), String> {
+    match File::create(file_location) {
+        Ok(mut file) => file.write_all(data).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string())
+    }
+}
+
 pub fn load_from_bytes_parallel<T : Indexable + ByteConvertable>(bytes : &[u8]) -> ParallelList<T> {
     let mut index = 0;
     let size = read_i32(bytes, &mut index) as usize;
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::loader::read_i32;
+use crate::objects::pathing::node::Node;
+use crate::objects::pathing::solver::{Solver, SOUTH_AFRICA_UTC_OFFSET};
+use crate::traits::ByteConvertable;
+use crate::types::{Cost, Index};
+
+pub const MINUTES_PER_DAY : u32 = 24 * 60;
+/// Days of outages, counting from today, that a schedule is turned into intervals for.
+pub const SCHEDULE_HORIZON_DAYS : u64 = 7;
+/// Minutes of the slots that outages start and end on, the flags changing on the hour and schedules on the half hour.
+pub const OUTAGE_SLOT_MINUTES : f64 = 30.0;
+
+/// A stretch of time the lights are out, from `start` up to but excluding `end`, both in minutes since the Unix epoch.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OutageInterval {
+    pub start : u32,
+    pub end : u32
+}
+
+impl OutageInterval {
+    /// Sorts the intervals and merges the ones that overlap or touch, so that they can be binary searched.
+    pub fn normalize(mut intervals : Vec<OutageInterval>) -> Box<[OutageInterval]> {
+        intervals.retain(|interval| interval.start < interval.end);
+        intervals.sort_unstable_by_key(|interval| interval.start);
+        let mut merged : Vec<OutageInterval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+                _ => merged.push(interval)
+            }
+        }
+        merged.into_boxed_slice()
+    }
+
+    /// The interval of the sorted, disjoint `intervals` that `minute` falls in, if any.
+    #[inline]
+    pub fn containing(intervals : &[OutageInterval], minute : u32) -> Option<&OutageInterval> {
+        let position = intervals.partition_point(|interval| interval.end <= minute);
+        intervals.get(position).filter(|interval| interval.start <= minute)
+    }
+}
+
+/// Minutes since the Unix epoch at local midnight on `date`, for a time zone `utc_offset` hours ahead of UTC.
+#[inline]
+fn midnight_minute(date : NaiveDate, utc_offset : Cost) -> f64 {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("Midnight exists on every date").and_utc().timestamp();
+    midnight as f64 / 60.0 - utc_offset as f64 * 60.0
+}
+
+/// One scheduled outage for a supply block on a day of the month. Windows that cross midnight end past
+/// `MINUTES_PER_DAY`, so 22:00 to 00:30 runs from minute 1320 to 1470.
+#[derive(Clone, Copy)]
+pub struct OutageWindow {
+    /// The lowest stage the outage happens at. Schedules are cumulative, so it also happens at every stage above.
+    pub stage : u8,
+    pub day : u8,
+    pub block : Index,
+    pub start : u32,
+    pub end : u32
+}
+
+/// A monthly load shedding schedule, repeating on the same days of every month, with the supply block every suburb
+/// belongs to and the stage currently in force. Stage 0 means no load shedding at all.
+pub struct LoadSheddingSchedule {
+    pub stage : u8,
+    /// Hours the local time the schedule is given in runs ahead of UTC, South African time unless set otherwise.
+    pub utc_offset : Cost,
+    blocks : HashMap<Index, Index>,
+    windows : Box<[OutageWindow]>
+}
+
+impl LoadSheddingSchedule {
+    pub fn new(stage : u8, blocks : HashMap<Index, Index>, mut windows : Vec<OutageWindow>) -> Self {
+        windows.sort_unstable_by_key(|window| (window.block, window.day, window.start));
+        Self {
+            stage,
+            utc_offset : SOUTH_AFRICA_UTC_OFFSET,
+            blocks,
+            windows : windows.into_boxed_slice()
+        }
+    }
+
+    /// The supply block of a suburb, if the schedule covers it.
+    #[inline]
+    pub fn block_of(&self, suburb : Index) -> Option<Index> {
+        self.blocks.get(&suburb).copied()
+    }
+
+    /// Every outage of `block` that starts on `day` of the month, at any stage.
+    pub fn windows(&self, block : Index, day : u32) -> &[OutageWindow] {
+        let start = self.windows.partition_point(|window| (window.block, window.day as u32) < (block, day));
+        let end = self.windows.partition_point(|window| (window.block, window.day as u32) <= (block, day));
+        &self.windows[start..end]
+    }
+
+    /// The outages of `block` at the current stage for `days` days from `first_day`, as intervals in minutes since the
+    /// Unix epoch, with `first_day` in the local time of the schedule. Outages running into `first_day` from the day
+    /// before are included.
+    pub fn outage_intervals(&self, block : Index, first_day : NaiveDate, days : u64) -> Box<[OutageInterval]> {
+        let mut intervals = Vec::new();
+        if self.stage > 0 {
+            let start_day = first_day.pred_opt().unwrap_or(first_day);
+            for date in start_day.iter_days().take(days as usize + 1) {
+                let midnight = midnight_minute(date, self.utc_offset) as u32;
+                for window in self.windows(block, date.day()) {
+                    if window.stage <= self.stage {
+                        intervals.push(OutageInterval {
+                            start : midnight + window.start,
+                            end : midnight + window.end
+                        });
+                    }
+                }
+            }
+        }
+        OutageInterval::normalize(intervals)
+    }
+}
+
+impl ByteConvertable for LoadSheddingSchedule {
+    /// The stage, the number of suburbs followed by `(suburb, block)` pairs, then the number of outages followed by
+    /// `(stage, day, block, start minute, end minute)` for each, all as 32 bit integers.
+    fn from_bytes(byte_array: &[u8]) -> Self {
+        let mut index = 0;
+        let stage = read_i32(byte_array, &mut index) as u8;
+        let suburb_count = read_i32(byte_array, &mut index) as usize;
+        let mut blocks = HashMap::with_capacity(suburb_count);
+        for _ in 0..suburb_count {
+            let suburb = read_i32(byte_array, &mut index) as Index;
+            let block = read_i32(byte_array, &mut index) as Index;
+            blocks.insert(suburb, block);
+        }
+        let window_count = read_i32(byte_array, &mut index) as usize;
+        let mut windows = Vec::with_capacity(window_count);
+        for _ in 0..window_count {
+            windows.push(OutageWindow {
+                stage : read_i32(byte_array, &mut index) as u8,
+                day : read_i32(byte_array, &mut index) as u8,
+                block : read_i32(byte_array, &mut index) as Index,
+                start : read_i32(byte_array, &mut index) as u32,
+                end : read_i32(byte_array, &mut index) as u32
+            });
+        }
+        Self::new(stage, blocks, windows)
+    }
+}
+
+/// What a search needs to know to tell whether the lights at a node are out: where local midnight on the departure
+/// date falls, and how long after it the search departs. The times a search asks about count in hours from that
+/// midnight.
+#[derive(Clone, Copy)]
+pub struct OutageClock {
+    /// Minutes since the Unix epoch at local midnight on the departure date.
+    pub midnight : f64,
+    /// Hours since midnight at departure.
+    pub departure : Cost
+}
+
+impl OutageClock {
+    /// The clock of a search departing at `time`, in a time zone `utc_offset` hours ahead of UTC.
+    pub fn new(time : DateTime<Utc>, utc_offset : Cost) -> Self {
+        Self {
+            midnight : midnight_minute(Solver::local_time(time, utc_offset).date(), utc_offset),
+            departure : Solver::time_of_day(time, utc_offset)
+        }
+    }
+
+    /// The outage slot of the departure, counted from the Unix epoch. Which lights are out only changes between slots.
+    #[inline]
+    pub fn slot(&self) -> i64 {
+        ((self.midnight + self.departure as f64 * 60.0) / OUTAGE_SLOT_MINUTES).floor() as i64
+    }
+
+    /// Hours from `time`, counted from midnight on the departure date, until the lights at `node` come back on, or 0
+    /// when they are on. Found with a binary search over the node's outage intervals, while nodes without any fall
+    /// back to the hourly bits of their flag.
+    #[inline]
+    pub fn wait(&self, node : &Node, time : Cost) -> Cost {
+        if node.outages.is_empty() {
+            return Solver::flag_wait(node.flag, time);
+        }
+        let minute = self.midnight + time as f64 * 60.0;
+        if minute < 0.0 {
+            return 0.0;
+        }
+        match OutageInterval::containing(&node.outages, minute as u32) {
+            Some(interval) => ((interval.end as f64 - minute) / 60.0) as Cost,
+            None => 0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::simd::Simd;
+
+    fn schedule(windows : Vec<OutageWindow>) -> LoadSheddingSchedule {
+        LoadSheddingSchedule::new(1, HashMap::from([(0, 0)]), windows)
+    }
+
+    fn date(year : i32, month : u32, day : u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn window(stage : u8, day : u8, start : u32, end : u32) -> OutageWindow {
+        OutageWindow { stage, day, block : 0, start, end }
+    }
+
+    /// The interval of an outage from `start` to `end` minutes after local midnight on `date`.
+    fn interval(date : NaiveDate, start : u32, end : u32) -> OutageInterval {
+        let midnight = midnight_minute(date, SOUTH_AFRICA_UTC_OFFSET) as u32;
+        OutageInterval { start : midnight + start, end : midnight + end }
+    }
+
+    fn intervals(bounds : &[(u32, u32)]) -> Vec<OutageInterval> {
+        bounds.iter().map(|(start, end)| OutageInterval { start : *start, end : *end }).collect()
+    }
+
+    #[test]
+    fn normalize_sorts_and_merges_overlapping_and_touching_intervals() {
+        let normalized = OutageInterval::normalize(intervals(&[(30, 40), (10, 20), (20, 25), (15, 18), (50, 50), (60, 55), (41, 45)]));
+        assert_eq!(*normalized, *intervals(&[(10, 25), (30, 40), (41, 45)]));
+        assert!(OutageInterval::normalize(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn containing_includes_the_start_but_not_the_end() {
+        let outages = intervals(&[(10, 20), (30, 40)]);
+        for (minute, expected) in [(9, None), (10, Some(0)), (19, Some(0)), (20, None), (29, None), (30, Some(1)), (39, Some(1)), (40, None)] {
+            assert_eq!(OutageInterval::containing(&outages, minute), expected.map(|index| &outages[index]), "minute {minute}");
+        }
+        assert_eq!(OutageInterval::containing(&[], 10), None);
+    }
+
+    #[test]
+    fn node_outages_take_over_from_the_flag() {
+        let clock = OutageClock { midnight : 600.0, departure : 0.0 };
+        let mut node = Node::new(0, Simd::splat(0.0), Box::new([]));
+        // Out for the first hour after midnight by the flag alone.
+        node.flag = 1;
+        assert_eq!(clock.wait(&node, 0.5), 0.5);
+        node.outages = OutageInterval::normalize(intervals(&[(660, 690)]));
+        assert_eq!(clock.wait(&node, 0.5), 0.0);
+        assert_eq!(clock.wait(&node, 1.0), 0.5);
+        assert_eq!(clock.wait(&node, 1.25), 0.25);
+        assert_eq!(clock.wait(&node, 1.5), 0.0);
+        assert_eq!(clock.wait(&node, -20.0), 0.0);
+    }
+
+    #[test]
+    fn outages_past_midnight_run_into_the_first_day() {
+        let schedule = schedule(vec![window(1, 14, 1320, 1470), window(1, 15, 120, 240)]);
+        assert_eq!(*schedule.outage_intervals(0, date(2024, 1, 15), 1),
+                   [interval(date(2024, 1, 14), 1320, 1470), interval(date(2024, 1, 15), 120, 240)]);
+    }
+
+    #[test]
+    fn outages_repeat_on_the_days_of_every_month() {
+        let schedule = schedule(vec![window(1, 1, 600, 720), window(1, 31, 600, 720)]);
+        assert_eq!(*schedule.outage_intervals(0, date(2024, 1, 31), 2),
+                   [interval(date(2024, 1, 31), 600, 720), interval(date(2024, 2, 1), 600, 720)]);
+        // February has no 31st, so only the outage on March 1 falls in these days.
+        assert_eq!(*schedule.outage_intervals(0, date(2023, 2, 28), 2), [interval(date(2023, 3, 1), 600, 720)]);
+        assert_eq!(*schedule.outage_intervals(0, date(2023, 12, 31), 2),
+                   [interval(date(2023, 12, 31), 600, 720), interval(date(2024, 1, 1), 600, 720)]);
+    }
+
+    #[test]
+    fn higher_stages_add_to_the_outages_of_lower_ones() {
+        let mut schedule = schedule(vec![window(2, 15, 600, 720), window(4, 15, 120, 240), window(4, 15, 720, 840)]);
+        let outages = |schedule : &LoadSheddingSchedule| schedule.outage_intervals(0, date(2024, 1, 15), 1);
+        assert_eq!(*outages(&schedule), []);
+        schedule.stage = 2;
+        assert_eq!(*outages(&schedule), [interval(date(2024, 1, 15), 600, 720)]);
+        schedule.stage = 8;
+        assert_eq!(*outages(&schedule), [interval(date(2024, 1, 15), 120, 240), interval(date(2024, 1, 15), 600, 840)]);
+        schedule.stage = 0;
+        assert_eq!(*outages(&schedule), []);
+    }
+
+    #[test]
+    fn outage_intervals_follow_the_schedule_utc_offset() {
+        let mut schedule = schedule(vec![OutageWindow { stage : 1, day : 15, block : 0, start : 600, end : 720 }]);
+        let midnight = (date(2024, 1, 15).and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() / 60) as u32;
+        assert_eq!(*schedule.outage_intervals(0, date(2024, 1, 15), 1), [OutageInterval { start : midnight + 480, end : midnight + 600 }]);
+        schedule.utc_offset = -3.0;
+        assert_eq!(*schedule.outage_intervals(0, date(2024, 1, 15), 1), [OutageInterval { start : midnight + 780, end : midnight + 900 }]);
+    }
+}
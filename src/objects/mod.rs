@@ -1,5 +1,6 @@
 pub mod traffic_light;
 pub mod suburb;
+pub mod load_shedding;
 pub mod boundary;
 pub mod pathing;
 pub mod util;
\ No newline at end of file
@@ -1,12 +1,59 @@
 use crate::types::{Cost, Index};
 
+/// Class of the road a connection runs along, from the fastest to the slowest.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[repr(u8)]
+pub enum RoadClass {
+    Motorway = 0,
+    Trunk = 1,
+    Primary = 2,
+    Secondary = 3,
+    Tertiary = 4,
+    Residential = 5,
+    Service = 6,
+    Unclassified = 7
+}
+
+impl RoadClass {
+    pub fn from_id(id : i32) -> Self {
+        match id {
+            0 => RoadClass::Motorway,
+            1 => RoadClass::Trunk,
+            2 => RoadClass::Primary,
+            3 => RoadClass::Secondary,
+            4 => RoadClass::Tertiary,
+            5 => RoadClass::Residential,
+            6 => RoadClass::Service,
+            _ => RoadClass::Unclassified
+        }
+    }
+
+    /// Speed in km/h assumed for connections that come without a speed limit.
+    pub fn default_speed(&self) -> u16 {
+        match self {
+            RoadClass::Motorway => 120,
+            RoadClass::Trunk => 100,
+            RoadClass::Primary => 80,
+            RoadClass::Secondary => 70,
+            RoadClass::Tertiary => 60,
+            RoadClass::Residential => 40,
+            RoadClass::Service => 20,
+            RoadClass::Unclassified => 60
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Connection {
     pub index : Index,
     pub cost : Cost,
-    pub speed : u16
+    /// Speed limit in km/h.
+    pub speed : u16,
+    pub road_class : RoadClass,
+    /// Whether the road only runs this way, in which case there is no connection back.
+    pub one_way : bool
 }
 
 unsafe impl Send for Connection {}
 
-unsafe impl Sync for Connection {}
\ No newline at end of file
+unsafe impl Sync for Connection {}
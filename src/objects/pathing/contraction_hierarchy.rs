@@ -0,0 +1,627 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::path::Path;
+
+use crate::loader::{ensure_remaining, read_f64, read_file, read_header, read_i32, write_f64, write_file, write_header, write_i32};
+use crate::new_slice;
+use crate::objects::pathing::node::{graph_checksum, Node};
+use crate::objects::pathing::node_type::{NodeType, SearchMethod};
+use crate::objects::util::super_cell::SuperCell;
+use crate::traits::ByteWritable;
+use crate::types::{Cost, Index};
+
+const WITNESS_SETTLE_LIMIT : usize = 500;
+const NO_EDGE : u32 = u32::MAX;
+/// Format version of the hierarchy files, raised whenever their layout changes.
+const FILE_VERSION : i32 = 1;
+/// Bytes taken by one edge in the hierarchy files.
+const EDGE_BYTES : usize = 32;
+
+/// An original connection, or a shortcut replacing the two edges `first` and `second`. Following the children of a
+/// shortcut down to the original connections gives back the chain of connections it stands for.
+#[derive(Clone, Copy)]
+pub struct HierarchyEdge {
+    pub source : Index,
+    pub target : Index,
+    pub cost : Cost,
+    pub distance : Cost,
+    pub first : u32,
+    pub second : u32
+}
+
+impl HierarchyEdge {
+    #[inline(always)]
+    pub fn is_shortcut(&self) -> bool {
+        self.first != NO_EDGE
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct QueueEntry {
+    pub cost : Cost,
+    pub index : Index
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    /// Reversed, so that `BinaryHeap` pops the lowest cost first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+/// Flattens per node edge lists, so that `offsets[i]..offsets[i + 1]` indexes the edges of node `i`.
+fn compress(lists : Vec<Vec<u32>>) -> (Box<[u32]>, Box<[u32]>) {
+    let mut offsets = Vec::with_capacity(lists.len() + 1);
+    let mut edges = Vec::new();
+    offsets.push(0u32);
+    for list in lists {
+        edges.extend_from_slice(&list);
+        offsets.push(edges.len() as u32);
+    }
+    (offsets.into_boxed_slice(), edges.into_boxed_slice())
+}
+
+struct Contractor {
+    outgoing : Vec<Vec<(Index, u32)>>,
+    incoming : Vec<Vec<(Index, u32)>>,
+    edges : Vec<HierarchyEdge>,
+    deleted_neighbours : Box<[i64]>,
+    witness_costs : Box<[Cost]>,
+    witness_touched : Vec<Index>
+}
+
+impl Contractor {
+    fn new(nodes : &[SuperCell<Node>], search_method : SearchMethod) -> Self {
+        let size = nodes.len();
+        let mut contractor = Self {
+            outgoing : vec![Vec::new(); size],
+            incoming : vec![Vec::new(); size],
+            edges : Vec::new(),
+            deleted_neighbours : new_slice(0i64, size),
+            witness_costs : new_slice(Cost::MAX, size),
+            witness_touched : Vec::new()
+        };
+        let cost_model = search_method.cost_model();
+        for cell in nodes {
+            let node = cell.get();
+            for connection in node.get_connections() {
+                if connection.index != node.index {
+                    let cost = cost_model.weight(connection, NodeType::Normal, 0.0);
+                    contractor.add_edge(HierarchyEdge {
+                        source : node.index,
+                        target : connection.index,
+                        cost,
+                        distance : connection.cost,
+                        first : NO_EDGE,
+                        second : NO_EDGE
+                    });
+                }
+            }
+        }
+        contractor
+    }
+
+    /// Adds the edge, replacing the edge between the same two nodes if the new one is cheaper.
+    fn add_edge(&mut self, edge : HierarchyEdge) {
+        let existing = self.outgoing[edge.source as usize].iter().position(|(target, _)| *target == edge.target);
+        match existing {
+            Some(position) => {
+                let existing_id = self.outgoing[edge.source as usize][position].1;
+                if self.edges[existing_id as usize].cost > edge.cost {
+                    let id = self.edges.len() as u32;
+                    self.edges.push(edge);
+                    self.outgoing[edge.source as usize][position].1 = id;
+                    if let Some(entry) = self.incoming[edge.target as usize].iter_mut().find(|(source, _)| *source == edge.source) {
+                        entry.1 = id;
+                    }
+                }
+            }
+            None => {
+                let id = self.edges.len() as u32;
+                self.edges.push(edge);
+                self.outgoing[edge.source as usize].push((edge.target, id));
+                self.incoming[edge.target as usize].push((edge.source, id));
+            }
+        }
+    }
+
+    /// Bounded Dijkstra over the uncontracted nodes from `source`, never passing through `via`.
+    fn witness_search(&mut self, source : Index, via : Index, max_cost : Cost) {
+        for index in self.witness_touched.drain(..) {
+            self.witness_costs[index as usize] = Cost::MAX;
+        }
+        let mut heap = BinaryHeap::new();
+        self.witness_costs[source as usize] = 0.0;
+        self.witness_touched.push(source);
+        heap.push(QueueEntry { cost: 0.0, index: source });
+        let mut settled = 0;
+        while let Some(QueueEntry { cost, index }) = heap.pop() {
+            if cost > self.witness_costs[index as usize] {
+                continue;
+            }
+            if cost > max_cost || settled > WITNESS_SETTLE_LIMIT {
+                break;
+            }
+            settled += 1;
+            for (target, id) in &self.outgoing[index as usize] {
+                if *target == via {
+                    continue;
+                }
+                let new_cost = cost + self.edges[*id as usize].cost;
+                if new_cost < self.witness_costs[*target as usize] {
+                    if self.witness_costs[*target as usize] == Cost::MAX {
+                        self.witness_touched.push(*target);
+                    }
+                    self.witness_costs[*target as usize] = new_cost;
+                    heap.push(QueueEntry { cost: new_cost, index: *target });
+                }
+            }
+        }
+    }
+
+    /// Finds the shortcuts needed to remove `node` from the remaining graph.
+    fn shortcuts(&mut self, node : Index) -> Vec<HierarchyEdge> {
+        let mut shortcuts = Vec::new();
+        let incoming = self.incoming[node as usize].clone();
+        let outgoing = self.outgoing[node as usize].clone();
+        let max_outgoing = outgoing.iter().map(|(_, id)| self.edges[*id as usize].cost).fold(0.0, Cost::max);
+        for (source, in_id) in incoming {
+            let in_edge = self.edges[in_id as usize];
+            self.witness_search(source, node, in_edge.cost + max_outgoing);
+            for (target, out_id) in &outgoing {
+                if *target == source {
+                    continue;
+                }
+                let out_edge = self.edges[*out_id as usize];
+                let cost = in_edge.cost + out_edge.cost;
+                if self.witness_costs[*target as usize] > cost {
+                    shortcuts.push(HierarchyEdge {
+                        source,
+                        target : *target,
+                        cost,
+                        distance : in_edge.distance + out_edge.distance,
+                        first : in_id,
+                        second : *out_id
+                    });
+                }
+            }
+        }
+        shortcuts
+    }
+
+    fn priority(&mut self, node : Index) -> i64 {
+        let removed = (self.incoming[node as usize].len() + self.outgoing[node as usize].len()) as i64;
+        self.shortcuts(node).len() as i64 - removed + self.deleted_neighbours[node as usize]
+    }
+
+    /// Removes `node` from the remaining graph, returning the edge ids leaving and entering it.
+    fn contract(&mut self, node : Index) -> (Vec<u32>, Vec<u32>) {
+        for shortcut in self.shortcuts(node) {
+            self.add_edge(shortcut);
+        }
+        let outgoing = std::mem::take(&mut self.outgoing[node as usize]);
+        let incoming = std::mem::take(&mut self.incoming[node as usize]);
+        for (target, _) in &outgoing {
+            self.incoming[*target as usize].retain(|(source, _)| *source != node);
+            self.deleted_neighbours[*target as usize] += 1;
+        }
+        for (source, _) in &incoming {
+            self.outgoing[*source as usize].retain(|(target, _)| *target != node);
+            self.deleted_neighbours[*source as usize] += 1;
+        }
+        (outgoing.into_iter().map(|(_, id)| id).collect(), incoming.into_iter().map(|(_, id)| id).collect())
+    }
+}
+
+/// Contraction hierarchy over the free flow weights of a `SearchMethod`, meaning load-shedding penalties are not part
+/// of the hierarchy.
+pub struct ContractionHierarchy {
+    pub search_method : SearchMethod,
+    /// `graph_checksum` of the nodes the hierarchy was built for.
+    pub checksum : u64,
+    pub ranks : Box<[u32]>,
+    pub edges : Box<[HierarchyEdge]>,
+    upward_offsets : Box<[u32]>,
+    upward : Box<[u32]>,
+    downward_offsets : Box<[u32]>,
+    downward : Box<[u32]>
+}
+
+impl ContractionHierarchy {
+    pub fn new(nodes : &[SuperCell<Node>], search_method : SearchMethod) -> Self {
+        let size = nodes.len();
+        let mut contractor = Contractor::new(nodes, search_method);
+        let mut queue = BinaryHeap::with_capacity(size);
+        for index in 0..size as Index {
+            queue.push(Reverse((contractor.priority(index), index)));
+        }
+        let mut ranks = new_slice(0u32, size);
+        let mut upward = vec![Vec::new(); size];
+        let mut downward = vec![Vec::new(); size];
+        let mut rank = 0u32;
+        while let Some(Reverse((_, node))) = queue.pop() {
+            let priority = contractor.priority(node);
+            if let Some(Reverse((next_priority, _))) = queue.peek() {
+                if priority > *next_priority {
+                    queue.push(Reverse((priority, node)));
+                    continue;
+                }
+            }
+            let (outgoing, incoming) = contractor.contract(node);
+            upward[node as usize] = outgoing;
+            downward[node as usize] = incoming;
+            ranks[node as usize] = rank;
+            rank += 1;
+        }
+        let (upward_offsets, upward) = compress(upward);
+        let (downward_offsets, downward) = compress(downward);
+        Self {
+            search_method,
+            checksum : graph_checksum(nodes),
+            ranks,
+            edges : contractor.edges.into_boxed_slice(),
+            upward_offsets,
+            upward,
+            downward_offsets,
+            downward
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ranks.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.ranks.is_empty()
+    }
+
+    /// Edge ids leaving `index` towards higher ranked nodes.
+    #[inline(always)]
+    pub fn get_upward(&self, index : Index) -> &[u32] {
+        &self.upward[self.upward_offsets[index as usize] as usize..self.upward_offsets[index as usize + 1] as usize]
+    }
+
+    /// Edge ids entering `index` from higher ranked nodes.
+    #[inline(always)]
+    pub fn get_downward(&self, index : Index) -> &[u32] {
+        &self.downward[self.downward_offsets[index as usize] as usize..self.downward_offsets[index as usize + 1] as usize]
+    }
+
+    /// Appends the target of every original connection `edge` stands for, in travel order.
+    pub fn unpack(&self, edge : u32, path : &mut Vec<Index>) {
+        let mut stack = vec![edge];
+        while let Some(current) = stack.pop() {
+            let hierarchy_edge = &self.edges[current as usize];
+            if hierarchy_edge.is_shortcut() {
+                stack.push(hierarchy_edge.second);
+                stack.push(hierarchy_edge.first);
+            } else {
+                path.push(hierarchy_edge.target);
+            }
+        }
+    }
+
+    /// The hierarchy file stored next to the node file, e.g. `cache/nodes.ch` for `cache/nodes.dat`.
+    pub fn file_location(nodes_file : &str) -> Box<Path> {
+        Path::new(nodes_file).with_extension("ch").into_boxed_path()
+    }
+
+    pub fn save(&self, file_location : &Path) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        self.to_bytes(&mut bytes);
+        write_file(file_location, &bytes)
+    }
+
+    /// Loads a previously saved hierarchy, failing if it is unreadable or was built for other nodes or another search
+    /// method.
+    pub fn load(file_location : &Path, nodes : &[SuperCell<Node>], search_method : SearchMethod) -> Result<Self, String> {
+        let hierarchy = Self::from_bytes(&read_file(file_location)?)?;
+        if hierarchy.len() != nodes.len() {
+            return Err(format!("Hierarchy has {} nodes, but {} nodes are loaded", hierarchy.len(), nodes.len()));
+        }
+        if hierarchy.checksum != graph_checksum(nodes) {
+            return Err("Hierarchy was built for a different graph".to_string());
+        }
+        if hierarchy.search_method != search_method {
+            return Err(format!("Hierarchy was built for {:?}, not {search_method:?}", hierarchy.search_method));
+        }
+        Ok(hierarchy)
+    }
+}
+
+fn read_u32_list(byte_array : &[u8], index : &mut usize, length : usize) -> Result<Box<[u32]>, String> {
+    ensure_remaining(byte_array, *index, length.saturating_mul(4))?;
+    let mut list = new_slice(0u32, length);
+    for value in list.iter_mut() {
+        *value = read_i32(byte_array, index) as u32;
+    }
+    Ok(list)
+}
+
+/// Reads a list written by `write_u32_list`.
+fn read_counted_u32_list(byte_array : &[u8], index : &mut usize) -> Result<Box<[u32]>, String> {
+    ensure_remaining(byte_array, *index, 4)?;
+    let length = read_i32(byte_array, index) as usize;
+    read_u32_list(byte_array, index, length)
+}
+
+fn write_u32_list(byte_array : &mut Vec<u8>, list : &[u32]) {
+    write_i32(byte_array, list.len() as i32);
+    for value in list {
+        write_i32(byte_array, *value as i32);
+    }
+}
+
+impl ByteWritable for ContractionHierarchy {
+    fn to_bytes(&self, byte_array: &mut Vec<u8>) {
+        write_header(byte_array, FILE_VERSION, self.checksum);
+        write_i32(byte_array, self.search_method.id());
+        write_u32_list(byte_array, &self.ranks);
+        write_i32(byte_array, self.edges.len() as i32);
+        for edge in self.edges.iter() {
+            write_i32(byte_array, edge.source as i32);
+            write_i32(byte_array, edge.target as i32);
+            write_f64(byte_array, edge.cost as f64);
+            write_f64(byte_array, edge.distance as f64);
+            write_i32(byte_array, edge.first as i32);
+            write_i32(byte_array, edge.second as i32);
+        }
+        write_u32_list(byte_array, &self.upward_offsets);
+        write_u32_list(byte_array, &self.upward);
+        write_u32_list(byte_array, &self.downward_offsets);
+        write_u32_list(byte_array, &self.downward);
+    }
+}
+
+impl ContractionHierarchy {
+    /// Reads a hierarchy written by `to_bytes`, failing on another format version or a truncated file.
+    pub fn from_bytes(byte_array: &[u8]) -> Result<Self, String> {
+        let mut index = 0;
+        let checksum = read_header(byte_array, &mut index, FILE_VERSION)?;
+        ensure_remaining(byte_array, index, 4)?;
+        let search_method = SearchMethod::from_id(read_i32(byte_array, &mut index));
+        let ranks = read_counted_u32_list(byte_array, &mut index)?;
+        ensure_remaining(byte_array, index, 4)?;
+        let edge_count = read_i32(byte_array, &mut index) as usize;
+        ensure_remaining(byte_array, index, edge_count.saturating_mul(EDGE_BYTES))?;
+        let mut edges = Vec::with_capacity(edge_count);
+        for _ in 0..edge_count {
+            edges.push(HierarchyEdge {
+                source : read_i32(byte_array, &mut index) as Index,
+                target : read_i32(byte_array, &mut index) as Index,
+                cost : read_f64(byte_array, &mut index) as Cost,
+                distance : read_f64(byte_array, &mut index) as Cost,
+                first : read_i32(byte_array, &mut index) as u32,
+                second : read_i32(byte_array, &mut index) as u32
+            });
+        }
+        let upward_offsets = read_counted_u32_list(byte_array, &mut index)?;
+        let upward = read_counted_u32_list(byte_array, &mut index)?;
+        let downward_offsets = read_counted_u32_list(byte_array, &mut index)?;
+        let downward = read_counted_u32_list(byte_array, &mut index)?;
+        if upward_offsets.len() != ranks.len() + 1 || downward_offsets.len() != ranks.len() + 1 {
+            return Err(format!("Hierarchy has {} ranks, but offsets for {} nodes", ranks.len(), upward_offsets.len().saturating_sub(1)));
+        }
+        Ok(Self {
+            search_method,
+            checksum,
+            ranks,
+            edges : edges.into_boxed_slice(),
+            upward_offsets,
+            upward,
+            downward_offsets,
+            downward
+        })
+    }
+}
+
+/// Reusable state for bidirectional upward searches, only resetting the nodes the previous query touched. Index 0 of
+/// each pair is the forward search from the start node, index 1 the backward search from the end node.
+pub struct HierarchyQuery {
+    costs : [Box<[Cost]>; 2],
+    parent_edges : [Box<[u32]>; 2],
+    heaps : [BinaryHeap<QueueEntry>; 2],
+    touched : Vec<Index>,
+    best_cost : Cost,
+    meeting_node : Index,
+    pub settled_nodes : u32
+}
+
+impl HierarchyQuery {
+    pub fn new(size : usize) -> Self {
+        Self {
+            costs : [new_slice(Cost::MAX, size), new_slice(Cost::MAX, size)],
+            parent_edges : [new_slice(NO_EDGE, size), new_slice(NO_EDGE, size)],
+            heaps : [BinaryHeap::new(), BinaryHeap::new()],
+            touched : Vec::new(),
+            best_cost : Cost::MAX,
+            meeting_node : Index::MAX,
+            settled_nodes : 0
+        }
+    }
+
+    fn reset(&mut self) {
+        for index in self.touched.drain(..) {
+            for direction in 0..2 {
+                self.costs[direction][index as usize] = Cost::MAX;
+                self.parent_edges[direction][index as usize] = NO_EDGE;
+            }
+        }
+        self.heaps[0].clear();
+        self.heaps[1].clear();
+        self.best_cost = Cost::MAX;
+        self.meeting_node = Index::MAX;
+        self.settled_nodes = 0;
+    }
+
+    #[inline(always)]
+    fn is_open(&self, direction : usize) -> bool {
+        self.heaps[direction].peek().is_some_and(|entry| entry.cost < self.best_cost)
+    }
+
+    fn visit(&mut self, direction : usize, index : Index, cost : Cost, edge_id : u32) {
+        if self.costs[0][index as usize] == Cost::MAX && self.costs[1][index as usize] == Cost::MAX {
+            self.touched.push(index);
+        }
+        self.costs[direction][index as usize] = cost;
+        self.parent_edges[direction][index as usize] = edge_id;
+        self.heaps[direction].push(QueueEntry { cost, index });
+    }
+
+    fn settle_next(&mut self, hierarchy : &ContractionHierarchy, direction : usize) {
+        let QueueEntry { cost, index } = self.heaps[direction].pop().expect("Heap was open, but had nothing to pop.");
+        if cost > self.costs[direction][index as usize] {
+            return;
+        }
+        self.settled_nodes += 1;
+        let other_cost = self.costs[1 - direction][index as usize];
+        if other_cost != Cost::MAX && cost + other_cost < self.best_cost {
+            self.best_cost = cost + other_cost;
+            self.meeting_node = index;
+        }
+        let edges = if direction == 0 { hierarchy.get_upward(index) } else { hierarchy.get_downward(index) };
+        for edge_id in edges {
+            let edge = &hierarchy.edges[*edge_id as usize];
+            let next = if direction == 0 { edge.target } else { edge.source };
+            let new_cost = cost + edge.cost;
+            if new_cost < self.costs[direction][next as usize] {
+                self.visit(direction, next, new_cost, *edge_id);
+            }
+        }
+    }
+
+    /// Finds the cheapest path in `hierarchy`, returned from the end node to the start node like `Solver::backtrack`,
+    /// together with its distance and cost.
+    pub fn find_path(&mut self, hierarchy : &ContractionHierarchy, start : Index, end : Index) -> Option<(Box<[Index]>, Cost, Cost)> {
+        self.reset();
+        self.visit(0, start, 0.0, NO_EDGE);
+        self.visit(1, end, 0.0, NO_EDGE);
+        let mut direction = 1;
+        loop {
+            let forward_open = self.is_open(0);
+            let backward_open = self.is_open(1);
+            if !forward_open && !backward_open {
+                break;
+            }
+            direction = match (forward_open, backward_open) {
+                (true, true) => 1 - direction,
+                (true, false) => 0,
+                _ => 1
+            };
+            self.settle_next(hierarchy, direction);
+        }
+        if self.meeting_node == Index::MAX {
+            return None;
+        }
+        let mut chain = Vec::new();
+        let mut current = self.meeting_node;
+        while self.parent_edges[0][current as usize] != NO_EDGE {
+            let edge_id = self.parent_edges[0][current as usize];
+            chain.push(edge_id);
+            current = hierarchy.edges[edge_id as usize].source;
+        }
+        chain.reverse();
+        current = self.meeting_node;
+        while self.parent_edges[1][current as usize] != NO_EDGE {
+            let edge_id = self.parent_edges[1][current as usize];
+            chain.push(edge_id);
+            current = hierarchy.edges[edge_id as usize].target;
+        }
+        let mut path = vec![start];
+        let mut distance = 0.0;
+        for edge_id in chain {
+            distance += hierarchy.edges[edge_id as usize].distance;
+            hierarchy.unpack(edge_id, &mut path);
+        }
+        path.reverse();
+        Some((path.into_boxed_slice(), distance, self.best_cost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::load_shedding::OutageInterval;
+    use crate::objects::pathing::node_type::{NodeType, SearchAlgorithm};
+    use crate::objects::pathing::solver::Solver;
+    use crate::objects::pathing::test_graph::{assert_matches_dijkstra, assert_rejects_truncated, grid, search_at, with_saved_file};
+    use chrono::DateTime;
+
+    #[test]
+    fn costs_match_dijkstra() {
+        let nodes = grid(6, 6, 1);
+        let hierarchy = ContractionHierarchy::new(nodes.get_slice(), SearchMethod::FASTEST);
+        let mut contraction = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        contraction.set_contraction_hierarchy(&hierarchy);
+        contraction.search_algorithm = SearchAlgorithm::CONTRACTION;
+        assert_matches_dijkstra(&mut contraction, |_, end, expected| assert_eq!(expected.is_some(), end != 36));
+    }
+
+    #[test]
+    fn routes_through_lights_that_are_out_unlike_dijkstra() {
+        let nodes = grid(5, 5, 0);
+        let departure = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (departure.timestamp() / 60) as u32;
+        let traffic_light = nodes.get_slice()[12].get_mut();
+        traffic_light.node_type = NodeType::AtTrafficLight;
+        traffic_light.outages = Box::new([OutageInterval { start : minute, end : minute + 120 }]);
+        let hierarchy = ContractionHierarchy::new(nodes.get_slice(), SearchMethod::AVOID);
+        let mut dijkstra = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::AVOID);
+        let mut contraction = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::AVOID);
+        contraction.set_contraction_hierarchy(&hierarchy);
+        contraction.search_algorithm = SearchAlgorithm::CONTRACTION;
+        let expected = search_at(&mut dijkstra, 7, 17, departure).expect("No path");
+        let cost = search_at(&mut contraction, 7, 17, departure).expect("No path");
+        assert!(!dijkstra.get_path_segments().iter().any(|segment| segment.from == 12));
+        assert!(contraction.get_path_segments().iter().any(|segment| segment.from == 12));
+        assert!(cost > expected * 2.0, "{cost} against {expected}");
+        assert_eq!(contraction.get_path_segments().last().map(|segment| segment.arrival), Some(cost));
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let nodes = grid(5, 4, 0);
+        let hierarchy = ContractionHierarchy::new(nodes.get_slice(), SearchMethod::FASTEST);
+        let (loaded, other_graph, other_method) = with_saved_file("ch", |file_location| hierarchy.save(file_location), |file_location| (
+            ContractionHierarchy::load(file_location, nodes.get_slice(), SearchMethod::FASTEST),
+            ContractionHierarchy::load(file_location, grid(4, 5, 0).get_slice(), SearchMethod::FASTEST),
+            ContractionHierarchy::load(file_location, nodes.get_slice(), SearchMethod::SHORTEST)
+        ));
+        let loaded = loaded.expect("Failed to load hierarchy");
+        assert!(other_graph.is_err() && other_method.is_err());
+        assert_eq!(loaded.ranks, hierarchy.ranks);
+        assert_eq!(loaded.edges.len(), hierarchy.edges.len());
+        for index in 0..hierarchy.len() as Index {
+            assert_eq!(loaded.get_upward(index), hierarchy.get_upward(index));
+            assert_eq!(loaded.get_downward(index), hierarchy.get_downward(index));
+        }
+        for (first, second) in loaded.edges.iter().zip(hierarchy.edges.iter()) {
+            assert_eq!((first.source, first.target, first.first, first.second), (second.source, second.target, second.first, second.second));
+            assert_eq!((first.cost, first.distance), (second.cost, second.distance));
+        }
+    }
+
+    #[test]
+    fn truncated_or_unversioned_files_fail_to_load() {
+        let hierarchy = ContractionHierarchy::new(grid(3, 3, 0).get_slice(), SearchMethod::FASTEST);
+        let mut bytes = Vec::new();
+        hierarchy.to_bytes(&mut bytes);
+        assert_rejects_truncated(&bytes, ContractionHierarchy::from_bytes);
+    }
+}
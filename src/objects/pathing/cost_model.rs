@@ -0,0 +1,134 @@
+use crate::objects::pathing::connection::Connection;
+use crate::objects::pathing::node_type::NodeType;
+use crate::objects::pathing::travel_time::TravelTimeFunction;
+use crate::types::Cost;
+
+#[inline]
+fn is_non_negative(value : Cost) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+/// Where the speed a connection is driven at comes from.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SpeedSource {
+    /// The speed limit of every connection, in km/h.
+    Connection,
+    /// The same speed in km/h everywhere, which makes the weights proportional to distance.
+    Fixed(Cost)
+}
+
+/// How a search weighs a connection. Leaving a node near or at a traffic light takes `penalty` times as long again
+/// while its lights are out, as described by `TravelTimeFunction`, and leaving a node at a traffic light costs
+/// `intersection_delay` seconds on top, lights or not.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct CostModel {
+    pub near_traffic_light : Cost,
+    pub at_traffic_light : Cost,
+    pub intersection_delay : Cost,
+    pub speed : SpeedSource
+}
+
+impl CostModel {
+    /// A model with the given penalties, a delay in seconds and a speed source, none of which may be negative.
+    pub fn new(near_traffic_light : Cost, at_traffic_light : Cost, intersection_delay : Cost, speed : SpeedSource) -> Result<Self, String> {
+        if !(is_non_negative(near_traffic_light) && is_non_negative(at_traffic_light)) {
+            return Err(format!("Penalties must not be negative, got {near_traffic_light} and {at_traffic_light}"));
+        }
+        if !is_non_negative(intersection_delay) {
+            return Err(format!("Intersection delay must not be negative, got {intersection_delay}"));
+        }
+        if let SpeedSource::Fixed(speed) = speed {
+            if !is_non_negative(speed) || speed == 0.0 {
+                return Err(format!("Fixed speed must be positive, got {speed}"));
+            }
+        }
+        Ok(Self {
+            near_traffic_light,
+            at_traffic_light,
+            intersection_delay,
+            speed
+        })
+    }
+
+    /// Fraction of the free flow time added to a connection leaving a node of `node_type` while its lights are out.
+    #[inline(always)]
+    pub fn penalty(&self, node_type : NodeType) -> Cost {
+        match node_type {
+            NodeType::Normal => 0.0,
+            NodeType::NearTrafficLight => self.near_traffic_light,
+            NodeType::AtTrafficLight => self.at_traffic_light
+        }
+    }
+
+    /// Hours to drive a connection with the lights on.
+    #[inline(always)]
+    pub fn free_flow(&self, connection : &Connection) -> Cost {
+        match self.speed {
+            SpeedSource::Connection => connection.cost / (connection.speed as Cost),
+            SpeedSource::Fixed(speed) => connection.cost / speed
+        }
+    }
+
+    /// Weight of a connection leaving a node of `node_type`, entered `wait` hours before the node's traffic light
+    /// comes back on, or with the lights on when `wait` is 0.
+    #[inline]
+    pub fn weight(&self, connection : &Connection, node_type : NodeType, wait : Cost) -> Cost {
+        let free_flow = self.free_flow(connection);
+        if node_type == NodeType::Normal {
+            return free_flow;
+        }
+        let time = TravelTimeFunction::new(free_flow, self.penalty(node_type)).evaluate(wait);
+        match node_type {
+            NodeType::AtTrafficLight => time + self.intersection_delay / 3600.0,
+            _ => time
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::pathing::connection::RoadClass;
+
+    const CONNECTION : Connection = Connection {
+        index : 0,
+        cost : 2.0,
+        speed : 80,
+        road_class : RoadClass::Unclassified,
+        one_way : false
+    };
+
+    #[test]
+    fn new_rejects_negative_and_non_finite_values() {
+        assert!(CostModel::new(0.0, 0.0, 0.0, SpeedSource::Connection).is_ok());
+        assert!(CostModel::new(1.0, 2.0, 5.0, SpeedSource::Fixed(50.0)).is_ok());
+        for penalty in [-1.0, Cost::NAN, Cost::INFINITY] {
+            assert!(CostModel::new(penalty, 1.0, 0.0, SpeedSource::Connection).is_err());
+            assert!(CostModel::new(1.0, penalty, 0.0, SpeedSource::Connection).is_err());
+            assert!(CostModel::new(1.0, 1.0, penalty, SpeedSource::Connection).is_err());
+            assert!(CostModel::new(1.0, 1.0, 0.0, SpeedSource::Fixed(penalty)).is_err());
+        }
+        assert!(CostModel::new(1.0, 1.0, 0.0, SpeedSource::Fixed(0.0)).is_err());
+    }
+
+    #[test]
+    fn weight_adds_penalties_and_delays_by_node_type() {
+        let model = CostModel::new(1.0, 3.0, 36.0, SpeedSource::Connection).unwrap();
+        assert_eq!(model.free_flow(&CONNECTION), 0.025);
+        assert_eq!(model.weight(&CONNECTION, NodeType::Normal, 1.0), 0.025);
+        assert_eq!(model.weight(&CONNECTION, NodeType::NearTrafficLight, 0.0), 0.025);
+        assert_eq!(model.weight(&CONNECTION, NodeType::NearTrafficLight, 1.0), 0.05);
+        assert_eq!(model.weight(&CONNECTION, NodeType::AtTrafficLight, 0.0), 0.035);
+        assert_eq!(model.weight(&CONNECTION, NodeType::AtTrafficLight, 1.0), 0.11);
+        // Waiting a minute for the lights beats crawling through at four times the free flow time.
+        assert!((model.weight(&CONNECTION, NodeType::AtTrafficLight, 1.0 / 60.0) - (0.035 + 1.0 / 60.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fixed_speeds_weigh_distance() {
+        let model = CostModel::new(0.0, 0.0, 0.0, SpeedSource::Fixed(40.0)).unwrap();
+        assert_eq!(model.free_flow(&CONNECTION), 0.05);
+        assert_eq!(model.free_flow(&Connection { speed : 120, ..CONNECTION }), 0.05);
+        assert_eq!(model.weight(&CONNECTION, NodeType::AtTrafficLight, 1.0), 0.05);
+    }
+}
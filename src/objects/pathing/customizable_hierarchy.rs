@@ -0,0 +1,488 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
+
+use crate::loader::{ensure_remaining, read_file, read_header, read_i32, write_file, write_header, write_i32};
+use crate::new_slice;
+use crate::objects::load_shedding::OutageClock;
+use crate::objects::pathing::contraction_hierarchy::QueueEntry;
+use crate::objects::pathing::node::{graph_checksum, Node};
+use crate::objects::pathing::node_type::SearchMethod;
+use crate::objects::util::super_cell::SuperCell;
+use crate::traits::ByteWritable;
+use crate::types::{Cost, Index};
+
+const NO_MIDDLE : Index = Index::MAX;
+/// Format version of the topology files, raised whenever their layout changes.
+const FILE_VERSION : i32 = 1;
+
+/// Orders the nodes by repeatedly eliminating the node with the fewest remaining neighbours, turning those
+/// neighbours into a clique. Returns the rank of every node and the higher ranked neighbours it had when eliminated.
+fn eliminate(nodes : &[SuperCell<Node>]) -> (Box<[u32]>, Vec<Vec<Index>>) {
+    let size = nodes.len();
+    let mut neighbours : Vec<Vec<Index>> = vec![Vec::new(); size];
+    for cell in nodes {
+        let node = cell.get();
+        for connection in node.get_connections() {
+            if connection.index != node.index {
+                neighbours[node.index as usize].push(connection.index);
+                neighbours[connection.index as usize].push(node.index);
+            }
+        }
+    }
+    for list in neighbours.iter_mut() {
+        list.sort_unstable();
+        list.dedup();
+    }
+    let mut queue : BinaryHeap<Reverse<(usize, Index)>> = (0..size as Index).map(|index| Reverse((neighbours[index as usize].len(), index))).collect();
+    let mut eliminated = vec![false; size];
+    let mut ranks = new_slice(0u32, size);
+    let mut upper = vec![Vec::new(); size];
+    let mut rank = 0u32;
+    while let Some(Reverse((degree, node))) = queue.pop() {
+        if eliminated[node as usize] || degree != neighbours[node as usize].len() {
+            continue;
+        }
+        eliminated[node as usize] = true;
+        ranks[node as usize] = rank;
+        rank += 1;
+        let clique = std::mem::take(&mut neighbours[node as usize]);
+        for member in &clique {
+            let list = &mut neighbours[*member as usize];
+            list.retain(|other| *other != node);
+            for other in &clique {
+                if other != member {
+                    if let Err(position) = list.binary_search(other) {
+                        list.insert(position, *other);
+                    }
+                }
+            }
+            queue.push(Reverse((list.len(), *member)));
+        }
+        upper[node as usize] = clique;
+    }
+    (ranks, upper)
+}
+
+/// Customizable contraction hierarchy. The node order and shortcut topology only depend on the road network, while
+/// the weights are filled in by `customize`, which is cheap enough to rerun whenever the load-shedding flags change or
+/// a search departs in another outage slot.
+pub struct CustomizableHierarchy {
+    pub search_method : SearchMethod,
+    /// `graph_checksum` of the nodes the topology was built for.
+    pub checksum : u64,
+    /// `OutageClock::slot` the weights were customized for, if they are up to date.
+    customized_slot : Option<i64>,
+    pub ranks : Box<[u32]>,
+    offsets : Box<[u32]>,
+    targets : Box<[Index]>,
+    up_weights : Box<[Cost]>,
+    down_weights : Box<[Cost]>,
+    up_distances : Box<[Cost]>,
+    down_distances : Box<[Cost]>,
+    up_middles : Box<[Index]>,
+    down_middles : Box<[Index]>
+}
+
+impl CustomizableHierarchy {
+    pub fn new(nodes : &[SuperCell<Node>], search_method : SearchMethod) -> Self {
+        let (ranks, upper) = eliminate(nodes);
+        let mut offsets = Vec::with_capacity(nodes.len() + 1);
+        let mut targets = Vec::new();
+        offsets.push(0u32);
+        for list in upper {
+            targets.extend_from_slice(&list);
+            offsets.push(targets.len() as u32);
+        }
+        Self::from_topology(search_method, graph_checksum(nodes), ranks, offsets.into_boxed_slice(), targets.into_boxed_slice())
+    }
+
+    fn from_topology(search_method : SearchMethod, checksum : u64, ranks : Box<[u32]>, offsets : Box<[u32]>, targets : Box<[Index]>) -> Self {
+        let arcs = targets.len();
+        Self {
+            search_method,
+            checksum,
+            customized_slot : None,
+            ranks,
+            offsets,
+            targets,
+            up_weights : new_slice(Cost::MAX, arcs),
+            down_weights : new_slice(Cost::MAX, arcs),
+            up_distances : new_slice(0.0, arcs),
+            down_distances : new_slice(0.0, arcs),
+            up_middles : new_slice(NO_MIDDLE, arcs),
+            down_middles : new_slice(NO_MIDDLE, arcs)
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ranks.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.ranks.is_empty()
+    }
+
+    #[inline(always)]
+    fn arcs(&self, index : Index) -> std::ops::Range<usize> {
+        self.offsets[index as usize] as usize..self.offsets[index as usize + 1] as usize
+    }
+
+    /// The arc between two neighbouring nodes, and whether travelling from `from` to `to` goes up the hierarchy.
+    #[inline]
+    fn find_arc(&self, from : Index, to : Index) -> Option<(usize, bool)> {
+        let upward = self.ranks[from as usize] < self.ranks[to as usize];
+        let (lower, higher) = if upward { (from, to) } else { (to, from) };
+        let range = self.arcs(lower);
+        let start = range.start;
+        self.targets[range].binary_search(&higher).ok().map(|position| (start + position, upward))
+    }
+
+    #[inline]
+    fn relax(&mut self, arc : usize, upward : bool, weight : Cost, distance : Cost, middle : Index) {
+        let (weights, distances, middles) = if upward {
+            (&mut self.up_weights, &mut self.up_distances, &mut self.up_middles)
+        } else {
+            (&mut self.down_weights, &mut self.down_distances, &mut self.down_middles)
+        };
+        if weight < weights[arc] {
+            weights[arc] = weight;
+            distances[arc] = distance;
+            middles[arc] = middle;
+        }
+    }
+
+    #[inline]
+    fn weight(&self, arc : usize, upward : bool) -> (Cost, Cost) {
+        if upward {
+            (self.up_weights[arc], self.up_distances[arc])
+        } else {
+            (self.down_weights[arc], self.down_distances[arc])
+        }
+    }
+
+    /// Customizes the hierarchy for the departure of `outage_clock`, unless it already is for the same outage slot.
+    pub fn customize_for(&mut self, nodes : &[SuperCell<Node>], outage_clock : &OutageClock) {
+        if self.customized_slot != Some(outage_clock.slot()) {
+            self.customize(nodes, outage_clock);
+        }
+    }
+
+    /// Marks the weights out of date after the node flags or outages changed, so that `customize_for` reweighs them.
+    pub fn invalidate(&mut self) {
+        self.customized_slot = None;
+    }
+
+    /// Weighs every connection with the current node types and the outages `outage_clock` reports at its departure,
+    /// then carries the weights up the hierarchy through the lower triangle of every arc.
+    pub fn customize(&mut self, nodes : &[SuperCell<Node>], outage_clock : &OutageClock) {
+        self.customized_slot = Some(outage_clock.slot());
+        self.up_weights.fill(Cost::MAX);
+        self.down_weights.fill(Cost::MAX);
+        self.up_middles.fill(NO_MIDDLE);
+        self.down_middles.fill(NO_MIDDLE);
+        let cost_model = self.search_method.cost_model();
+        for cell in nodes {
+            let node = cell.get();
+            for connection in node.get_connections() {
+                if let Some((arc, upward)) = self.find_arc(node.index, connection.index) {
+                    let weight = cost_model.weight(connection, node.node_type, outage_clock.wait(node, outage_clock.departure));
+                    self.relax(arc, upward, weight, connection.cost, NO_MIDDLE);
+                }
+            }
+        }
+        let mut order : Vec<Index> = (0..self.len() as Index).collect();
+        order.sort_unstable_by_key(|index| self.ranks[*index as usize]);
+        for middle in order {
+            let range = self.arcs(middle);
+            for first in range.clone() {
+                let (to_middle, to_middle_distance) = self.weight(first, false);
+                if to_middle == Cost::MAX {
+                    continue;
+                }
+                for second in range.clone() {
+                    let (from_middle, from_middle_distance) = self.weight(second, true);
+                    if first == second || from_middle == Cost::MAX {
+                        continue;
+                    }
+                    let from = self.targets[first];
+                    let to = self.targets[second];
+                    if let Some((arc, upward)) = self.find_arc(from, to) {
+                        self.relax(arc, upward, to_middle + from_middle, to_middle_distance + from_middle_distance, middle);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends every node after `from` on the original connections the arc from `from` to `to` stands for.
+    fn unpack(&self, from : Index, to : Index, path : &mut Vec<Index>) {
+        let mut stack = vec![(from, to)];
+        while let Some((current_from, current_to)) = stack.pop() {
+            let (arc, upward) = self.find_arc(current_from, current_to).expect("Unpacked a hop that is not an arc of the hierarchy");
+            let middle = if upward { self.up_middles[arc] } else { self.down_middles[arc] };
+            if middle == NO_MIDDLE {
+                path.push(current_to);
+            } else {
+                stack.push((middle, current_to));
+                stack.push((current_from, middle));
+            }
+        }
+    }
+
+    /// The topology file stored next to the node file, e.g. `cache/nodes.cch` for `cache/nodes.dat`.
+    pub fn file_location(nodes_file : &str) -> Box<Path> {
+        Path::new(nodes_file).with_extension("cch").into_boxed_path()
+    }
+
+    pub fn save(&self, file_location : &Path) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        self.to_bytes(&mut bytes);
+        write_file(file_location, &bytes)
+    }
+
+    /// Loads a previously saved topology, failing if it is unreadable or was built for other nodes. The weights still
+    /// have to be filled in with `customize`.
+    pub fn load(file_location : &Path, nodes : &[SuperCell<Node>], search_method : SearchMethod) -> Result<Self, String> {
+        let mut hierarchy = Self::from_bytes(&read_file(file_location)?)?;
+        if hierarchy.len() != nodes.len() {
+            return Err(format!("Hierarchy has {} nodes, but {} nodes are loaded", hierarchy.len(), nodes.len()));
+        }
+        if hierarchy.checksum != graph_checksum(nodes) {
+            return Err("Hierarchy was built for a different graph".to_string());
+        }
+        hierarchy.search_method = search_method;
+        Ok(hierarchy)
+    }
+
+    /// Reads a topology written by `to_bytes`, failing on another format version, a truncated file or offsets that do
+    /// not fit the targets.
+    pub fn from_bytes(byte_array: &[u8]) -> Result<Self, String> {
+        let mut index = 0;
+        let checksum = read_header(byte_array, &mut index, FILE_VERSION)?;
+        let mut lists = Vec::with_capacity(3);
+        for _ in 0..3 {
+            ensure_remaining(byte_array, index, 4)?;
+            let length = read_i32(byte_array, &mut index) as usize;
+            ensure_remaining(byte_array, index, length.saturating_mul(4))?;
+            let mut list = new_slice(0u32, length);
+            for value in list.iter_mut() {
+                *value = read_i32(byte_array, &mut index) as u32;
+            }
+            lists.push(list);
+        }
+        let mut lists = lists.into_iter();
+        let ranks = lists.next().unwrap();
+        let offsets = lists.next().unwrap();
+        let targets = lists.next().unwrap();
+        if offsets.len() != ranks.len() + 1 || offsets.windows(2).any(|pair| pair[0] > pair[1]) || offsets.last().is_some_and(|last| *last as usize != targets.len()) {
+            return Err(format!("Hierarchy offsets do not fit its {} nodes and {} arcs", ranks.len(), targets.len()));
+        }
+        Ok(Self::from_topology(SearchMethod::FASTEST, checksum, ranks, offsets, targets))
+    }
+}
+
+impl ByteWritable for CustomizableHierarchy {
+    fn to_bytes(&self, byte_array: &mut Vec<u8>) {
+        write_header(byte_array, FILE_VERSION, self.checksum);
+        for list in [&self.ranks, &self.offsets, &self.targets] {
+            write_i32(byte_array, list.len() as i32);
+            for value in list.iter() {
+                write_i32(byte_array, *value as i32);
+            }
+        }
+    }
+}
+
+/// Reusable state for bidirectional upward searches over a customized hierarchy. Index 0 of each pair is the forward
+/// search from the start node, index 1 the backward search from the end node.
+pub struct CustomizableQuery {
+    costs : [Box<[Cost]>; 2],
+    parents : [Box<[Index]>; 2],
+    heaps : [BinaryHeap<QueueEntry>; 2],
+    touched : Vec<Index>,
+    best_cost : Cost,
+    meeting_node : Index,
+    pub settled_nodes : u32
+}
+
+impl CustomizableQuery {
+    pub fn new(size : usize) -> Self {
+        Self {
+            costs : [new_slice(Cost::MAX, size), new_slice(Cost::MAX, size)],
+            parents : [new_slice(Index::MAX, size), new_slice(Index::MAX, size)],
+            heaps : [BinaryHeap::new(), BinaryHeap::new()],
+            touched : Vec::new(),
+            best_cost : Cost::MAX,
+            meeting_node : Index::MAX,
+            settled_nodes : 0
+        }
+    }
+
+    fn reset(&mut self) {
+        for index in self.touched.drain(..) {
+            for direction in 0..2 {
+                self.costs[direction][index as usize] = Cost::MAX;
+                self.parents[direction][index as usize] = Index::MAX;
+            }
+        }
+        self.heaps[0].clear();
+        self.heaps[1].clear();
+        self.best_cost = Cost::MAX;
+        self.meeting_node = Index::MAX;
+        self.settled_nodes = 0;
+    }
+
+    #[inline(always)]
+    fn is_open(&self, direction : usize) -> bool {
+        self.heaps[direction].peek().is_some_and(|entry| entry.cost < self.best_cost)
+    }
+
+    fn visit(&mut self, direction : usize, index : Index, cost : Cost, parent : Index) {
+        if self.costs[0][index as usize] == Cost::MAX && self.costs[1][index as usize] == Cost::MAX {
+            self.touched.push(index);
+        }
+        self.costs[direction][index as usize] = cost;
+        self.parents[direction][index as usize] = parent;
+        self.heaps[direction].push(QueueEntry { cost, index });
+    }
+
+    fn settle_next(&mut self, hierarchy : &CustomizableHierarchy, direction : usize) {
+        let QueueEntry { cost, index } = self.heaps[direction].pop().expect("Heap was open, but had nothing to pop.");
+        if cost > self.costs[direction][index as usize] {
+            return;
+        }
+        self.settled_nodes += 1;
+        let other_cost = self.costs[1 - direction][index as usize];
+        if other_cost != Cost::MAX && cost + other_cost < self.best_cost {
+            self.best_cost = cost + other_cost;
+            self.meeting_node = index;
+        }
+        let weights = if direction == 0 { &hierarchy.up_weights } else { &hierarchy.down_weights };
+        for arc in hierarchy.arcs(index) {
+            if weights[arc] == Cost::MAX {
+                continue;
+            }
+            let next = hierarchy.targets[arc];
+            let new_cost = cost + weights[arc];
+            if new_cost < self.costs[direction][next as usize] {
+                self.visit(direction, next, new_cost, index);
+            }
+        }
+    }
+
+    /// Finds the cheapest path with the weights of the last customization, returned from the end node to the start
+    /// node like `Solver::backtrack`, together with its distance and cost.
+    pub fn find_path(&mut self, hierarchy : &CustomizableHierarchy, start : Index, end : Index) -> Option<(Box<[Index]>, Cost, Cost)> {
+        self.reset();
+        self.visit(0, start, 0.0, Index::MAX);
+        self.visit(1, end, 0.0, Index::MAX);
+        let mut direction = 1;
+        loop {
+            let forward_open = self.is_open(0);
+            let backward_open = self.is_open(1);
+            if !forward_open && !backward_open {
+                break;
+            }
+            direction = match (forward_open, backward_open) {
+                (true, true) => 1 - direction,
+                (true, false) => 0,
+                _ => 1
+            };
+            self.settle_next(hierarchy, direction);
+        }
+        if self.meeting_node == Index::MAX {
+            return None;
+        }
+        let mut hops = vec![self.meeting_node];
+        let mut current = self.meeting_node;
+        while self.parents[0][current as usize] != Index::MAX {
+            current = self.parents[0][current as usize];
+            hops.push(current);
+        }
+        hops.reverse();
+        current = self.meeting_node;
+        while self.parents[1][current as usize] != Index::MAX {
+            current = self.parents[1][current as usize];
+            hops.push(current);
+        }
+        let mut path = vec![start];
+        let mut distance = 0.0;
+        for hop in hops.windows(2) {
+            let (arc, upward) = hierarchy.find_arc(hop[0], hop[1]).expect("Search walked a hop that is not an arc of the hierarchy");
+            distance += hierarchy.weight(arc, upward).1;
+            hierarchy.unpack(hop[0], hop[1], &mut path);
+        }
+        path.reverse();
+        Some((path.into_boxed_slice(), distance, self.best_cost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::load_shedding::OutageInterval;
+    use crate::objects::pathing::node_type::{NodeType, SearchAlgorithm};
+    use crate::objects::pathing::solver::Solver;
+    use crate::objects::pathing::test_graph::{assert_matches_dijkstra, assert_rejects_truncated, grid, same_cost, search_at, with_saved_file};
+    use chrono::{DateTime, TimeDelta};
+
+    #[test]
+    fn costs_match_dijkstra() {
+        let nodes = grid(6, 6, 1);
+        let hierarchy = SuperCell::new(CustomizableHierarchy::new(nodes.get_slice(), SearchMethod::FASTEST));
+        let mut customizable = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        customizable.set_customizable_hierarchy(&hierarchy);
+        customizable.search_algorithm = SearchAlgorithm::CUSTOMIZABLE;
+        assert_matches_dijkstra(&mut customizable, |_, _, _| {});
+    }
+
+    #[test]
+    fn customizes_again_for_departures_in_other_slots() {
+        let nodes = grid(5, 5, 0);
+        let outage_start = DateTime::from_timestamp(1_700_000_000 / 1800 * 1800, 0).unwrap();
+        let minute = (outage_start.timestamp() / 60) as u32;
+        let traffic_light = nodes.get_slice()[12].get_mut();
+        traffic_light.node_type = NodeType::AtTrafficLight;
+        traffic_light.outages = Box::new([OutageInterval { start : minute, end : minute + 120 }]);
+        let hierarchy = SuperCell::new(CustomizableHierarchy::new(nodes.get_slice(), SearchMethod::AVOID));
+        let mut dijkstra = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::AVOID);
+        let mut customizable = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::AVOID);
+        customizable.set_customizable_hierarchy(&hierarchy);
+        customizable.search_algorithm = SearchAlgorithm::CUSTOMIZABLE;
+        for offset in [-60, 10, 150, 70, -30] {
+            let departure = outage_start + TimeDelta::minutes(offset);
+            let expected = search_at(&mut dijkstra, 7, 17, departure);
+            let cost = search_at(&mut customizable, 7, 17, departure);
+            assert!(same_cost(expected, cost), "Departing {offset} minutes after the outage: {expected:?} != {cost:?}");
+            let through_light = customizable.get_path_segments().iter().any(|segment| segment.from == 12);
+            assert_eq!(through_light, !(0..120).contains(&offset));
+        }
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let nodes = grid(5, 4, 0);
+        let hierarchy = CustomizableHierarchy::new(nodes.get_slice(), SearchMethod::FASTEST);
+        let (loaded, other_graph) = with_saved_file("cch", |file_location| hierarchy.save(file_location), |file_location| (
+            CustomizableHierarchy::load(file_location, nodes.get_slice(), SearchMethod::AVOID),
+            CustomizableHierarchy::load(file_location, grid(4, 5, 0).get_slice(), SearchMethod::FASTEST)
+        ));
+        let loaded = loaded.expect("Failed to load hierarchy");
+        assert!(other_graph.is_err());
+        assert_eq!(loaded.search_method, SearchMethod::AVOID);
+        assert_eq!(loaded.ranks, hierarchy.ranks);
+        assert_eq!(loaded.offsets, hierarchy.offsets);
+        assert_eq!(loaded.targets, hierarchy.targets);
+    }
+
+    #[test]
+    fn truncated_or_unversioned_files_fail_to_load() {
+        let hierarchy = CustomizableHierarchy::new(grid(3, 3, 0).get_slice(), SearchMethod::FASTEST);
+        let mut bytes = Vec::new();
+        hierarchy.to_bytes(&mut bytes);
+        assert_rejects_truncated(&bytes, CustomizableHierarchy::from_bytes);
+    }
+}
@@ -0,0 +1,165 @@
+use std::simd::Simd;
+
+use crate::objects::pathing::node::Node;
+use crate::objects::pathing::node_type::{NodeType, AT_TRAFFIC_LIGHT_THRESHOLD};
+use crate::objects::pathing::solver::Solver;
+use crate::objects::traffic_light::TrafficLight;
+use crate::objects::util::quad_tree::QuadTree;
+use crate::objects::util::super_cell::SuperCell;
+use crate::types::{Cost, Index, Pos};
+use crate::MULTIPLIER;
+
+/// Bearing changes, in degrees, up to which a driver carries on straight, bears, turns and turns sharply.
+const STRAIGHT_ANGLE : Pos = 20.0;
+const SLIGHT_ANGLE : Pos = 60.0;
+const TURN_ANGLE : Pos = 120.0;
+const SHARP_ANGLE : Pos = 170.0;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Maneuver {
+    Depart,
+    Straight,
+    SlightLeft,
+    Left,
+    SharpLeft,
+    SlightRight,
+    Right,
+    SharpRight,
+    UTurn,
+    Arrive
+}
+
+impl Maneuver {
+    /// The maneuver for a change of bearing of `angle` degrees, positive to the left.
+    pub fn from_angle(angle : Pos) -> Self {
+        match angle.abs() {
+            change if change < STRAIGHT_ANGLE => Maneuver::Straight,
+            change if change >= SHARP_ANGLE => Maneuver::UTurn,
+            change if angle > 0.0 && change < SLIGHT_ANGLE => Maneuver::SlightLeft,
+            change if angle > 0.0 && change < TURN_ANGLE => Maneuver::Left,
+            _ if angle > 0.0 => Maneuver::SharpLeft,
+            change if change < SLIGHT_ANGLE => Maneuver::SlightRight,
+            change if change < TURN_ANGLE => Maneuver::Right,
+            _ => Maneuver::SharpRight
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        match self {
+            Maneuver::Depart => 0,
+            Maneuver::Straight => 1,
+            Maneuver::SlightLeft => 2,
+            Maneuver::Left => 3,
+            Maneuver::SharpLeft => 4,
+            Maneuver::SlightRight => 5,
+            Maneuver::Right => 6,
+            Maneuver::SharpRight => 7,
+            Maneuver::UTurn => 8,
+            Maneuver::Arrive => 9
+        }
+    }
+}
+
+/// One step of a route description.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub maneuver : Maneuver,
+    /// Distance driven since the previous instruction, in the units of the path distance.
+    pub distance : Cost,
+    /// Node of the path the maneuver happens at.
+    pub node : Index,
+    /// Id of the traffic light at `node`, if there is one.
+    pub traffic_light : Option<Index>,
+    /// Whether that traffic light will be out when `node` is reached.
+    pub warning : bool
+}
+
+/// Change of bearing in degrees, positive to the left, from `from -> via` to `via -> to`.
+fn bearing_change(from : &Node, via : &Node, to : &Node) -> Pos {
+    let incoming : Simd<Pos, 2> = (via.position - from.position) * MULTIPLIER;
+    let outgoing : Simd<Pos, 2> = (to.position - via.position) * MULTIPLIER;
+    let cross = incoming[0] * outgoing[1] - incoming[1] * outgoing[0];
+    let dot = incoming[0] * outgoing[0] + incoming[1] * outgoing[1];
+    cross.atan2(dot).to_degrees()
+}
+
+/// The traffic light in `traffic_light_tree` that put `node` next to one, if any is close enough.
+fn find_traffic_light(traffic_light_tree : &QuadTree<SuperCell<TrafficLight>>, node : &Node) -> Option<Index> {
+    traffic_light_tree.find_nearest(&node.position, Some(AT_TRAFFIC_LIGHT_THRESHOLD)).map(|cell| cell.get().id)
+}
+
+/// Describes the path the solver found as maneuvers. Every intersection the path turns at and every traffic light
+/// it passes gets an instruction, the traffic lights named by their id from `traffic_light_tree` when one is given.
+/// A traffic light is flagged when its lights are out at the arrival time at its node. Roads that merely bend
+/// between intersections do not count as turns. Empty when the solver has no path.
+pub fn describe_path(solver : &Solver, traffic_light_tree : Option<&QuadTree<SuperCell<TrafficLight>>>) -> Box<[Instruction]> {
+    let Some((path, _, _)) = solver.get_path_as_indices() else {
+        return Box::new([]);
+    };
+    let nodes = solver.get_nodes();
+    let segments = solver.get_path_segments();
+    let outage_clock = solver.outage_clock();
+    let mut instructions = vec![Instruction {
+        maneuver : Maneuver::Depart,
+        distance : 0.0,
+        node : path[path.len() - 1],
+        traffic_light : None,
+        warning : false
+    }];
+    let mut distance = 0.0;
+    for (position, segment) in segments.iter().enumerate() {
+        distance += segment.distance;
+        let Some(next) = segments.get(position + 1) else {
+            break;
+        };
+        let from = nodes[segment.from as usize].get();
+        let via = nodes[segment.to as usize].get();
+        let to = nodes[next.to as usize].get();
+        let maneuver = Maneuver::from_angle(bearing_change(from, via, to));
+        let at_traffic_light = via.node_type == NodeType::AtTrafficLight;
+        let at_intersection = via.get_connections().len() > 2 && maneuver != Maneuver::Straight;
+        if at_traffic_light || at_intersection {
+            instructions.push(Instruction {
+                maneuver,
+                distance,
+                node : via.index,
+                traffic_light : traffic_light_tree.filter(|_| at_traffic_light).and_then(|tree| find_traffic_light(tree, via)),
+                warning : at_traffic_light && outage_clock.wait(via, outage_clock.departure + segment.arrival) > 0.0
+            });
+            distance = 0.0;
+        }
+    }
+    instructions.push(Instruction {
+        maneuver : Maneuver::Arrive,
+        distance,
+        node : path[0],
+        traffic_light : None,
+        warning : false
+    });
+    instructions.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maneuvers_change_at_the_angle_boundaries() {
+        let expected = [
+            (0.0, Maneuver::Straight, Maneuver::Straight),
+            (19.9, Maneuver::Straight, Maneuver::Straight),
+            (STRAIGHT_ANGLE, Maneuver::SlightLeft, Maneuver::SlightRight),
+            (59.9, Maneuver::SlightLeft, Maneuver::SlightRight),
+            (SLIGHT_ANGLE, Maneuver::Left, Maneuver::Right),
+            (119.9, Maneuver::Left, Maneuver::Right),
+            (TURN_ANGLE, Maneuver::SharpLeft, Maneuver::SharpRight),
+            (169.9, Maneuver::SharpLeft, Maneuver::SharpRight),
+            (SHARP_ANGLE, Maneuver::UTurn, Maneuver::UTurn),
+            (180.0, Maneuver::UTurn, Maneuver::UTurn)
+        ];
+        for (angle, left, right) in expected {
+            assert_eq!(Maneuver::from_angle(angle), left, "{angle} degrees to the left");
+            assert_eq!(Maneuver::from_angle(-angle), right, "{angle} degrees to the right");
+        }
+    }
+}
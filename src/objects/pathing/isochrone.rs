@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+use std::simd::Simd;
+
+use crate::objects::pathing::node::Node;
+use crate::objects::pathing::solver::Solver;
+use crate::objects::suburb::Suburb;
+use crate::objects::util::super_cell::SuperCell;
+use crate::types::{Index, Pos};
+use crate::{distance, MULTIPLIER};
+
+type Corner = (i32, i32);
+
+/// Grid size in metres used when no other resolution is asked for, roughly a city block.
+pub const DEFAULT_RESOLUTION : Pos = 150.0;
+
+/// Everything reachable from a source node within a cutoff, together with a polygon around it that can be tested
+/// with `Suburb::is_inside` and drawn like any other suburb.
+pub struct Isochrone {
+    pub source : Index,
+    pub nodes : Box<[Index]>,
+    pub polygon : Suburb
+}
+
+impl Isochrone {
+    /// Runs a search bounded by `cutoff`, in the units of the solver's `CONVERSION_FACTOR` (seconds), and outlines the
+    /// settled nodes. `resolution` is the size in metres of the grid the outline follows, smaller values hug the roads
+    /// closer at the cost of a more jagged polygon.
+    pub fn new(solver : &mut Solver, source : Index, cutoff : u32, resolution : Pos) -> Self {
+        let nodes = solver.bounded_search(source, cutoff);
+        let polygon = concave_hull(solver.get_nodes(), &nodes, resolution, source);
+        Self {
+            source,
+            nodes,
+            polygon
+        }
+    }
+}
+
+/// Removes and returns the end of an edge leaving `corner`. Where two cells only touch diagonally a corner has two
+/// edges leaving it, and turning right keeps both cells inside the same ring.
+fn take_edge(outgoing : &mut HashMap<Corner, Vec<Corner>>, corner : Corner, direction : Corner) -> Corner {
+    let ends = outgoing.get_mut(&corner).expect("Every boundary corner has as many edges leaving it as entering it");
+    let right = (corner.0 + direction.1, corner.1 - direction.0);
+    let position = ends.iter().position(|end| *end == right).unwrap_or(0);
+    let end = ends.swap_remove(position);
+    if ends.is_empty() {
+        outgoing.remove(&corner);
+    }
+    end
+}
+
+/// Twice the signed area of a ring, positive when it runs counter-clockwise.
+fn signed_area(ring : &[Corner]) -> i64 {
+    (0..ring.len()).map(|index| {
+        let (x1, y1) = ring[index];
+        let (x2, y2) = ring[(index + 1) % ring.len()];
+        x1 as i64 * y2 as i64 - x2 as i64 * y1 as i64
+    }).sum()
+}
+
+/// Outlines the reached nodes by marking every grid cell that a node, or a connection between two reached nodes,
+/// passes through, and tracing the outer boundary of the marked cells. The result is a closed ring of points.
+fn concave_hull(nodes : &[SuperCell<Node>], reached : &[Index], resolution : Pos, id : Index) -> Suburb {
+    let cell_size = Simd::splat(resolution) / MULTIPLIER;
+    let origin = nodes[reached[0] as usize].get().position;
+    let to_cell = |position : Simd<Pos, 2>| -> Corner {
+        let offset = (position - origin) / cell_size;
+        (offset[0].floor() as i32, offset[1].floor() as i32)
+    };
+    let reached_set : HashSet<Index> = reached.iter().copied().collect();
+    let mut cells = HashSet::new();
+    for index in reached {
+        let node = nodes[*index as usize].get();
+        cells.insert(to_cell(node.position));
+        for connection in node.get_connections() {
+            if reached_set.contains(&connection.index) {
+                let other = nodes[connection.index as usize].get().position;
+                let steps = (distance(&node.position, &other) / resolution * 4.0).ceil() as usize;
+                for step in 1..steps {
+                    let fraction = Simd::splat(step as Pos / steps as Pos);
+                    cells.insert(to_cell(node.position + (other - node.position) * fraction));
+                }
+            }
+        }
+    }
+
+    // Cell sides that border an empty cell, directed so that the marked cell is on their left.
+    let mut outgoing : HashMap<Corner, Vec<Corner>> = HashMap::new();
+    for &(x, y) in &cells {
+        let sides = [
+            ((x, y - 1), (x, y), (x + 1, y)),
+            ((x + 1, y), (x + 1, y), (x + 1, y + 1)),
+            ((x, y + 1), (x + 1, y + 1), (x, y + 1)),
+            ((x - 1, y), (x, y + 1), (x, y))
+        ];
+        for (neighbour, start, end) in sides {
+            if !cells.contains(&neighbour) {
+                outgoing.entry(start).or_default().push(end);
+            }
+        }
+    }
+
+    let mut outline : Vec<Corner> = Vec::new();
+    let mut outline_area = 0;
+    while let Some(&start) = outgoing.keys().next() {
+        let mut ring = vec![start];
+        let mut previous = start;
+        let mut current = take_edge(&mut outgoing, start, (0, 0));
+        while current != start {
+            ring.push(current);
+            let direction = (current.0 - previous.0, current.1 - previous.1);
+            previous = current;
+            current = take_edge(&mut outgoing, current, direction);
+        }
+        let area = signed_area(&ring);
+        if area > outline_area {
+            outline_area = area;
+            outline = ring;
+        }
+    }
+
+    // Only keep the corners where the outline turns.
+    let corners : Vec<Corner> = (0..outline.len()).filter(|index| {
+        let (px, py) = outline[(index + outline.len() - 1) % outline.len()];
+        let (x, y) = outline[*index];
+        let (nx, ny) = outline[(index + 1) % outline.len()];
+        (x - px) * (ny - y) != (y - py) * (nx - x)
+    }).map(|index| outline[index]).collect();
+    let mut x_points = Vec::with_capacity(corners.len() + 1);
+    let mut y_points = Vec::with_capacity(corners.len() + 1);
+    for (x, y) in corners.iter().chain(corners.first()) {
+        x_points.push(origin[0] + *x as Pos * cell_size[0]);
+        y_points.push(origin[1] + *y as Pos * cell_size[1]);
+    }
+    Suburb::from_points(id, x_points.into_boxed_slice(), y_points.into_boxed_slice())
+}
@@ -0,0 +1,238 @@
+use std::path::Path;
+
+use crate::loader::{ensure_remaining, read_f64, read_file, read_header, read_i32, write_f64, write_file, write_header, write_i32};
+use crate::new_slice;
+use crate::objects::pathing::node::{graph_checksum, Node};
+use crate::objects::pathing::node_type::SearchMethod;
+use crate::objects::pathing::solver::Solver;
+use crate::objects::util::super_cell::SuperCell;
+use crate::traits::ByteWritable;
+use crate::types::{Cost, Index};
+
+/// Format version of the landmark files, raised whenever their layout changes.
+const FILE_VERSION : i32 = 1;
+
+/// The reachable node with the highest cost, ignoring nodes that are already covered at no cost.
+fn farthest(costs : &[Cost]) -> Option<Index> {
+    costs.iter().enumerate()
+        .filter(|(_, cost)| **cost != Cost::MAX && **cost > 0.0)
+        .max_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map(|(index, _)| index as Index)
+}
+
+/// Landmarks for the ALT heuristic, with the free flow cost from every landmark to every node and from every node back
+/// to every landmark. Because load shedding only ever adds to a connection's cost, the triangle inequality over these
+/// tables stays a lower bound at any time of day.
+pub struct Landmarks {
+    pub search_method : SearchMethod,
+    /// `graph_checksum` of the nodes the tables were computed for.
+    pub checksum : u64,
+    pub landmarks : Box<[Index]>,
+    node_count : usize,
+    from_landmarks : Box<[Cost]>,
+    to_landmarks : Box<[Cost]>
+}
+
+impl Landmarks {
+    /// Picks up to `count` landmarks farthest-first, each one the node farthest from every landmark picked so far, and
+    /// fills in their tables with `solver`, which needs a reverse graph for the backward costs.
+    pub fn new(solver : &mut Solver, count : usize) -> Self {
+        let node_count = solver.get_nodes().len();
+        let search_method = solver.search_method;
+        let mut landmarks = Vec::with_capacity(count);
+        let mut from_landmarks = Vec::with_capacity(count * node_count);
+        let mut to_landmarks = Vec::with_capacity(count * node_count);
+        let mut closest = new_slice(Cost::MAX, node_count);
+        let mut next = if node_count == 0 { None } else { farthest(&solver.free_flow_costs(0, false)) };
+        while let Some(landmark) = next {
+            if landmarks.len() == count {
+                break;
+            }
+            let from_landmark = solver.free_flow_costs(landmark, false);
+            let to_landmark = solver.free_flow_costs(landmark, true);
+            for (closest, cost) in closest.iter_mut().zip(from_landmark.iter()) {
+                *closest = closest.min(*cost);
+            }
+            landmarks.push(landmark);
+            from_landmarks.extend_from_slice(&from_landmark);
+            to_landmarks.extend_from_slice(&to_landmark);
+            next = farthest(&closest);
+        }
+        solver.reset();
+        Self {
+            search_method,
+            checksum : graph_checksum(solver.get_nodes()),
+            landmarks : landmarks.into_boxed_slice(),
+            node_count,
+            from_landmarks : from_landmarks.into_boxed_slice(),
+            to_landmarks : to_landmarks.into_boxed_slice()
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.node_count
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+
+    /// Lower bound of the cost from `index` to `end`, the largest the triangle inequality gives over all landmarks.
+    #[inline]
+    pub fn lower_bound(&self, index : Index, end : Index) -> Cost {
+        let mut bound = 0.0;
+        for landmark in 0..self.landmarks.len() {
+            let offset = landmark * self.node_count;
+            let from_node = self.from_landmarks[offset + index as usize];
+            let from_end = self.from_landmarks[offset + end as usize];
+            if from_node != Cost::MAX && from_end != Cost::MAX {
+                bound = Cost::max(bound, from_end - from_node);
+            }
+            let to_node = self.to_landmarks[offset + index as usize];
+            let to_end = self.to_landmarks[offset + end as usize];
+            if to_node != Cost::MAX && to_end != Cost::MAX {
+                bound = Cost::max(bound, to_node - to_end);
+            }
+        }
+        bound
+    }
+
+    /// The table file stored next to the node file, e.g. `cache/nodes.alt` for `cache/nodes.dat`.
+    pub fn file_location(nodes_file : &str) -> Box<Path> {
+        Path::new(nodes_file).with_extension("alt").into_boxed_path()
+    }
+
+    pub fn save(&self, file_location : &Path) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        self.to_bytes(&mut bytes);
+        write_file(file_location, &bytes)
+    }
+
+    /// Loads previously saved tables, failing if they are unreadable or were built for other nodes, another search
+    /// method or another number of landmarks.
+    pub fn load(file_location : &Path, nodes : &[SuperCell<Node>], count : usize, search_method : SearchMethod) -> Result<Self, String> {
+        let landmarks = Self::from_bytes(&read_file(file_location)?)?;
+        if landmarks.len() != nodes.len() {
+            return Err(format!("Landmarks cover {} nodes, but {} nodes are loaded", landmarks.len(), nodes.len()));
+        }
+        if landmarks.checksum != graph_checksum(nodes) {
+            return Err("Landmarks were computed for a different graph".to_string());
+        }
+        if landmarks.search_method != search_method {
+            return Err(format!("Landmarks were built for {:?}, not {search_method:?}", landmarks.search_method));
+        }
+        if landmarks.landmarks.len() != count {
+            return Err(format!("{} landmarks were saved, but {count} were requested", landmarks.landmarks.len()));
+        }
+        Ok(landmarks)
+    }
+}
+
+impl ByteWritable for Landmarks {
+    fn to_bytes(&self, byte_array: &mut Vec<u8>) {
+        write_header(byte_array, FILE_VERSION, self.checksum);
+        write_i32(byte_array, self.search_method.id());
+        write_i32(byte_array, self.node_count as i32);
+        write_i32(byte_array, self.landmarks.len() as i32);
+        for landmark in self.landmarks.iter() {
+            write_i32(byte_array, *landmark as i32);
+        }
+        for cost in self.from_landmarks.iter().chain(self.to_landmarks.iter()) {
+            write_f64(byte_array, *cost as f64);
+        }
+    }
+}
+
+impl Landmarks {
+    /// Reads tables written by `to_bytes`, failing on another format version, a truncated file or landmarks that are
+    /// not among the nodes.
+    pub fn from_bytes(byte_array: &[u8]) -> Result<Self, String> {
+        let mut index = 0;
+        let checksum = read_header(byte_array, &mut index, FILE_VERSION)?;
+        ensure_remaining(byte_array, index, 12)?;
+        let search_method = SearchMethod::from_id(read_i32(byte_array, &mut index));
+        let node_count = read_i32(byte_array, &mut index) as usize;
+        let count = read_i32(byte_array, &mut index) as usize;
+        let table_size = count.checked_mul(node_count).ok_or("Landmark tables are too large")?;
+        ensure_remaining(byte_array, index, table_size.saturating_mul(16).saturating_add(count.saturating_mul(4)))?;
+        let mut landmarks = new_slice(0 as Index, count);
+        for landmark in landmarks.iter_mut() {
+            *landmark = read_i32(byte_array, &mut index) as Index;
+        }
+        if let Some(landmark) = landmarks.iter().find(|landmark| **landmark as usize >= node_count) {
+            return Err(format!("Landmark {landmark} is not among the {node_count} nodes"));
+        }
+        let mut tables = [new_slice(0.0, table_size), new_slice(0.0, table_size)];
+        for table in tables.iter_mut() {
+            for cost in table.iter_mut() {
+                *cost = read_f64(byte_array, &mut index) as Cost;
+            }
+        }
+        let [from_landmarks, to_landmarks] = tables;
+        Ok(Self {
+            search_method,
+            checksum,
+            landmarks,
+            node_count,
+            from_landmarks,
+            to_landmarks
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::pathing::node_type::SearchAlgorithm;
+    use crate::objects::pathing::reverse_graph::ReverseGraph;
+    use crate::objects::pathing::test_graph::{assert_matches_dijkstra, assert_rejects_truncated, grid, with_saved_file};
+    use crate::objects::util::parallel_list::ParallelList;
+
+    fn landmarks(nodes : &ParallelList<Node>, count : usize) -> Landmarks {
+        let reverse_graph = ReverseGraph::new(nodes.get_slice());
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, u32::MAX, SearchMethod::FASTEST);
+        solver.set_reverse_graph(&reverse_graph);
+        Landmarks::new(&mut solver, count)
+    }
+
+    #[test]
+    fn costs_match_dijkstra() {
+        let nodes = grid(6, 6, 1);
+        let landmarks = landmarks(&nodes, 4);
+        assert_eq!(landmarks.landmarks.len(), 4);
+        let mut alt = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        alt.set_landmarks(&landmarks);
+        alt.search_algorithm = SearchAlgorithm::LANDMARKS;
+        assert_matches_dijkstra(&mut alt, |start, end, expected| {
+            if let Some(expected) = expected {
+                assert!(landmarks.lower_bound(start, end) <= expected * (1.0 + 1e-5));
+            }
+        });
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let nodes = grid(5, 4, 0);
+        let landmarks = landmarks(&nodes, 3);
+        let (loaded, other_graph, other_count) = with_saved_file("alt", |file_location| landmarks.save(file_location), |file_location| (
+            Landmarks::load(file_location, nodes.get_slice(), 3, SearchMethod::FASTEST),
+            Landmarks::load(file_location, grid(4, 5, 0).get_slice(), 3, SearchMethod::FASTEST),
+            Landmarks::load(file_location, nodes.get_slice(), 2, SearchMethod::FASTEST)
+        ));
+        let loaded = loaded.expect("Failed to load landmarks");
+        assert!(other_graph.is_err() && other_count.is_err());
+        assert_eq!(loaded.landmarks, landmarks.landmarks);
+        assert_eq!(loaded.from_landmarks, landmarks.from_landmarks);
+        assert_eq!(loaded.to_landmarks, landmarks.to_landmarks);
+    }
+
+    #[test]
+    fn truncated_or_unversioned_files_fail_to_load() {
+        let landmarks = landmarks(&grid(3, 3, 0), 2);
+        let mut bytes = Vec::new();
+        landmarks.to_bytes(&mut bytes);
+        assert_rejects_truncated(&bytes, Landmarks::from_bytes);
+    }
+}
@@ -1,4 +1,21 @@
 pub mod solver;
 pub mod node;
 pub mod connection;
-pub mod node_type;
\ No newline at end of file
+pub mod node_type;
+pub mod reverse_graph;
+pub mod contraction_hierarchy;
+pub mod customizable_hierarchy;
+pub mod landmarks;
+pub mod isochrone;
+pub mod turn_restriction;
+pub mod turns;
+pub mod travel_time;
+pub mod cost_model;
+pub mod path_segment;
+pub mod profile;
+pub mod instructions;
+pub mod route;
+pub mod stop_order;
+
+#[cfg(test)]
+pub mod test_graph;
\ No newline at end of file
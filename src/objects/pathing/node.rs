@@ -1,6 +1,8 @@
 use crate::loader::{read_f64, read_i32};
-use crate::objects::pathing::connection::Connection;
+use crate::objects::load_shedding::OutageInterval;
+use crate::objects::pathing::connection::{Connection, RoadClass};
 use crate::objects::pathing::node_type::NodeType;
+use crate::objects::util::super_cell::SuperCell;
 use crate::traits::{ByteConvertable, Indexable, Positional};
 use crate::types::{Cost, Flag, Index, Pos};
 use core::slice::SlicePattern;
@@ -10,6 +12,8 @@ use std::simd::Simd;
 pub struct Node {
     pub index : Index,
     pub flag : Flag,
+    /// Outages of the traffic light the node was assigned to, sorted and disjoint.
+    pub outages : Box<[OutageInterval]>,
     pub node_type : NodeType,
     pub position : Simd<Pos, 2>,
     pub connections : Box<[Connection]>
@@ -21,6 +25,7 @@ impl Node {
             index,
             position,
             flag : Flag::MIN,
+            outages : Box::new([]),
             node_type : NodeType::Normal,
             connections
         }
@@ -32,26 +37,76 @@ impl Node {
     }
 }
 
+/// FNV-1a hash of the ids, positions and connections of `nodes`, stored in the files derived from the graph so that
+/// files built for another graph are not loaded. Flags and outages are left out, as they change while running.
+pub fn graph_checksum(nodes : &[SuperCell<Node>]) -> u64 {
+    const PRIME : u64 = 0x100000001b3;
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut add = |value : u64| hash = (hash ^ value).wrapping_mul(PRIME);
+    add(nodes.len() as u64);
+    for cell in nodes {
+        let node = cell.get();
+        add(node.index as u64);
+        add(node.position[0].to_bits() as u64);
+        add(node.position[1].to_bits() as u64);
+        add(node.connections.len() as u64);
+        for connection in node.get_connections() {
+            add(connection.index as u64);
+            add(connection.cost.to_bits() as u64);
+            add(connection.speed as u64);
+            add(connection.road_class as u64);
+            add(connection.one_way as u64);
+        }
+    }
+    hash
+}
+
 unsafe impl Send for Node {}
 
 unsafe impl Sync for Node {}
 
+/// Value in the speed slot of a node record marking the revision where every connection carries its own speed, road
+/// class and one-way flag. Older records hold the speed of every connection leaving the node there instead.
+pub const PER_CONNECTION_FORMAT : i32 = -2;
+
 impl ByteConvertable for Node {
+    /// The id, x and y, then either a speed shared by every connection followed by the number of connections and
+    /// `(index, distance)` for each, or `PER_CONNECTION_FORMAT` followed by the number of connections and
+    /// `(index, distance, speed, road class, one way)` for each. A speed of 0 falls back to the road class. Connections in
+    /// the older records are taken to be two-way.
     fn from_bytes(byte_array: &[u8]) -> Self {
         let mut index = 0;
         let id = read_i32(byte_array, &mut index);
         let x = read_f64(byte_array, &mut index);
         let y = read_f64(byte_array, &mut index);
-        let speed = read_i32(byte_array, &mut index) as u16;
+        let speed = read_i32(byte_array, &mut index);
         let connected_indices_size = read_i32(byte_array, &mut index) as usize;
         let mut tmp_indices = Box::new_uninit_slice(connected_indices_size);
         unsafe {
             for index_c in 0..connected_indices_size {
-                tmp_indices[index_c] = MaybeUninit::new(Connection {
-                    index: read_i32(byte_array, &mut index) as Index,
-                    cost: read_f64(byte_array, &mut index) as Cost,
-                    speed
-                });
+                let connection_index = read_i32(byte_array, &mut index) as Index;
+                let cost = read_f64(byte_array, &mut index) as Cost;
+                let connection = if speed == PER_CONNECTION_FORMAT {
+                    let connection_speed = read_i32(byte_array, &mut index) as u16;
+                    let road_class = RoadClass::from_id(read_i32(byte_array, &mut index));
+                    let one_way = read_i32(byte_array, &mut index) != 0;
+                    Connection {
+                        index : connection_index,
+                        cost,
+                        speed : if connection_speed == 0 { road_class.default_speed() } else { connection_speed },
+                        road_class,
+                        one_way
+                    }
+                } else {
+                    Connection {
+                        index : connection_index,
+                        cost,
+                        speed : speed as u16,
+                        road_class : RoadClass::Unclassified,
+                        one_way : false
+                    }
+                };
+                tmp_indices[index_c] = MaybeUninit::new(connection);
             }
 
             Node::new(
@@ -68,6 +123,7 @@ impl Clone for Node {
         Self {
             index : self.index,
             flag : self.flag,
+            outages : self.outages.clone(),
             position : self.position,
             node_type: self.node_type,
             connections: self.connections.clone(),
@@ -86,4 +142,61 @@ impl Indexable for Node {
     fn index(&self) -> Index {
         self.index
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{write_f64, write_i32};
+    use crate::objects::pathing::test_graph::grid;
+
+    fn header(bytes : &mut Vec<u8>, speed : i32, connections : i32) {
+        write_i32(bytes, 7);
+        write_f64(bytes, 28.5);
+        write_f64(bytes, -26.25);
+        write_i32(bytes, speed);
+        write_i32(bytes, connections);
+    }
+
+    #[test]
+    fn reads_per_connection_records() {
+        let mut bytes = Vec::new();
+        header(&mut bytes, PER_CONNECTION_FORMAT, 2);
+        for (index, distance, speed, road_class, one_way) in [(3, 0.5, 50, 2, 1), (4, 1.25, 0, 5, 0)] {
+            write_i32(&mut bytes, index);
+            write_f64(&mut bytes, distance);
+            write_i32(&mut bytes, speed);
+            write_i32(&mut bytes, road_class);
+            write_i32(&mut bytes, one_way);
+        }
+        let node = Node::from_bytes(&bytes);
+        assert_eq!((node.index, node.position.to_array()), (7, [28.5, -26.25]));
+        let connections : Vec<_> = node.get_connections().iter()
+            .map(|connection| (connection.index, connection.cost, connection.speed, connection.road_class, connection.one_way))
+            .collect();
+        assert_eq!(connections, [(3, 0.5, 50, RoadClass::Primary, true), (4, 1.25, 40, RoadClass::Residential, false)]);
+    }
+
+    #[test]
+    fn reads_legacy_records_as_two_way() {
+        let mut bytes = Vec::new();
+        header(&mut bytes, 60, 2);
+        for (index, distance) in [(3, 0.5), (4, 1.25)] {
+            write_i32(&mut bytes, index);
+            write_f64(&mut bytes, distance);
+        }
+        let node = Node::from_bytes(&bytes);
+        assert_eq!((node.index, node.position.to_array()), (7, [28.5, -26.25]));
+        let connections : Vec<_> = node.get_connections().iter()
+            .map(|connection| (connection.index, connection.cost, connection.speed, connection.road_class, connection.one_way))
+            .collect();
+        assert_eq!(connections, [(3, 0.5, 60, RoadClass::Unclassified, false), (4, 1.25, 60, RoadClass::Unclassified, false)]);
+    }
+
+    #[test]
+    fn checksum_covers_the_one_way_flag() {
+        let nodes = grid(2, 1, 0);
+        let two_way = graph_checksum(nodes.get_slice());
+        nodes.get_slice()[0].get_mut().connections[0].one_way = true;
+        assert_ne!(graph_checksum(nodes.get_slice()), two_way);
+    }
+}
@@ -1,6 +1,7 @@
 use rayon::prelude::ParallelSlice;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use crate::distance;
+use crate::objects::pathing::cost_model::{CostModel, SpeedSource};
 use crate::objects::pathing::node::Node;
 use crate::objects::traffic_light::TrafficLight;
 use crate::objects::util::super_cell::SuperCell;
@@ -15,13 +16,73 @@ pub enum NodeType {
     Normal = 0
 }
 
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum SearchMethod {
     FASTEST,
     SHORTEST,
     AVOID
 }
 
-const AT_TRAFFIC_LIGHT_THRESHOLD: Pos = 25f64 as Pos;
+impl SearchMethod {
+    pub fn from_id(id : i32) -> Self {
+        match id {
+            0 => SearchMethod::FASTEST,
+            1 => SearchMethod::SHORTEST,
+            _ => SearchMethod::AVOID
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        match self {
+            SearchMethod::FASTEST => 0,
+            SearchMethod::SHORTEST => 1,
+            SearchMethod::AVOID => 2
+        }
+    }
+
+    /// The cost model the method stands for. FASTEST and AVOID drive at the speed limit and slow down at traffic
+    /// lights that are out, AVOID so much that it goes around them, while SHORTEST ignores both.
+    pub fn cost_model(&self) -> CostModel {
+        match self {
+            SearchMethod::FASTEST => CostModel {
+                near_traffic_light : 3.0,
+                at_traffic_light : 5.0,
+                intersection_delay : 0.0,
+                speed : SpeedSource::Connection
+            },
+            SearchMethod::SHORTEST => CostModel {
+                near_traffic_light : 0.0,
+                at_traffic_light : 0.0,
+                intersection_delay : 0.0,
+                speed : SpeedSource::Fixed(60.0)
+            },
+            SearchMethod::AVOID => CostModel {
+                near_traffic_light : 100.0,
+                at_traffic_light : 200.0,
+                intersection_delay : 0.0,
+                speed : SpeedSource::Connection
+            }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SearchAlgorithm {
+    DIJKSTRA,
+    ASTAR,
+    /// Meets a backward search over the weights with the lights on, and finishes forwards over the nodes it bounds, so
+    /// it finds the same cost as `DIJKSTRA`, load shedding included.
+    BIDIRECTIONAL,
+    /// Routes over the free flow weights of a contraction hierarchy, so load shedding does not change the route, and it
+    /// drives straight through lights that are out where `DIJKSTRA` would go around them. The cost reported is that of
+    /// driving the route from the departure, load shedding included. `CUSTOMIZABLE` routes around outages instead.
+    CONTRACTION,
+    CUSTOMIZABLE,
+    LANDMARKS,
+    EDGES
+}
+
+pub const AT_TRAFFIC_LIGHT_THRESHOLD: Pos = 25f64 as Pos;
 const NEAR_TRAFFIC_LIGHT_THRESHOLD: Pos = 100f64 as Pos;
 
 impl NodeType {
@@ -34,9 +95,11 @@ impl NodeType {
                     if distance < AT_TRAFFIC_LIGHT_THRESHOLD {
                         mutable_node.node_type = NodeType::AtTrafficLight;
                         mutable_node.flag = traffic_light.flag;
+                        mutable_node.outages = traffic_light.outages.clone();
                     } else if distance < NEAR_TRAFFIC_LIGHT_THRESHOLD {
                         mutable_node.node_type = NodeType::NearTrafficLight;
                         mutable_node.flag = traffic_light.flag;
+                        mutable_node.outages = traffic_light.outages.clone();
                     }
                 },
                 NodeType::NearTrafficLight => {
@@ -44,6 +107,7 @@ impl NodeType {
                     if distance < AT_TRAFFIC_LIGHT_THRESHOLD {
                         mutable_node.node_type = NodeType::AtTrafficLight;
                         mutable_node.flag = traffic_light.flag;
+                        mutable_node.outages = traffic_light.outages.clone();
                     }
                 },
                 NodeType::AtTrafficLight => {
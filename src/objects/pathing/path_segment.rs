@@ -0,0 +1,15 @@
+use crate::types::{Cost, Index};
+
+/// One connection of a path and what its cost is made of, all times in hours.
+#[derive(Clone, Copy, Debug)]
+pub struct PathSegment {
+    pub from : Index,
+    pub to : Index,
+    pub distance : Cost,
+    /// Time to drive the connection with the lights on.
+    pub base_time : Cost,
+    /// Time added by the traffic light the connection leaves from, for the lights being out or the intersection delay.
+    pub penalty : Cost,
+    /// Time after departure at which `to` is reached.
+    pub arrival : Cost
+}
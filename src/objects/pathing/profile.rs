@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+
+use crate::objects::pathing::solver::Solver;
+use crate::types::{Cost, Index};
+
+/// Minutes between the departures sampled before any refinement. Outages last hours, so nothing the lights do fits
+/// between two samples unnoticed.
+pub const PROFILE_STEP : u32 = 15;
+/// How far, in hours, a sampled travel time may stray from the line between its neighbours before the span between
+/// them is refined further. One second.
+const TOLERANCE : Cost = 1.0 / 3600.0;
+
+/// Travel time from one node to another as a function of when the trip starts, over a window of departures. Between
+/// two samples the travel time is linear in the departure, which is exact for the piecewise linear travel time
+/// functions of `TravelTimeFunction` down to the minute.
+pub struct Profile {
+    /// Departures in minutes since the Unix epoch, ascending. Departures from which the end cannot be reached are left
+    /// out.
+    pub departures : Box<[u32]>,
+    /// Travel time in hours for the departure at the same position.
+    pub durations : Box<[Cost]>
+}
+
+impl Profile {
+    /// Samples departures from `earliest` to `latest` every `PROFILE_STEP` minutes, then halves every span where the
+    /// travel time bends until it is linear or a minute wide. Every sample is a full time-dependent search with the
+    /// solver's current algorithm, which waits out the outages on the nodes set by
+    /// `associate_traffic_lights_to_nodes`.
+    pub fn new(solver : &mut Solver, start : Index, end : Index, earliest : DateTime<Utc>, latest : DateTime<Utc>) -> Self {
+        let first = (earliest.timestamp() / 60) as u32;
+        let last = ((latest.timestamp() / 60) as u32).max(first);
+        let mut sample = |minute : u32| -> Option<Cost> {
+            let departure = DateTime::from_timestamp(minute as i64 * 60, 0).expect("Departure out of range");
+            solver.travel_time(start, end, departure)
+        };
+        let mut samples = Vec::new();
+        let mut minute = first;
+        loop {
+            samples.push((minute, sample(minute)));
+            if minute == last {
+                break;
+            }
+            minute = (minute + PROFILE_STEP).min(last);
+        }
+
+        let mut refined = Vec::with_capacity(samples.len());
+        for pair in samples.windows(2) {
+            refined.push(pair[0]);
+            let mut spans = vec![(pair[0], pair[1])];
+            let mut found = Vec::new();
+            while let Some(((a, a_time), (b, b_time))) = spans.pop() {
+                if b - a <= 1 {
+                    continue;
+                }
+                let middle = a + (b - a) / 2;
+                let middle_time = sample(middle);
+                found.push((middle, middle_time));
+                let linear = match (a_time, middle_time, b_time) {
+                    (Some(a_time), Some(middle_time), Some(b_time)) => {
+                        let expected = a_time + (b_time - a_time) * (middle - a) as Cost / (b - a) as Cost;
+                        (middle_time - expected).abs() <= TOLERANCE
+                    }
+                    (None, None, None) => true,
+                    _ => false
+                };
+                if !linear {
+                    spans.push(((a, a_time), (middle, middle_time)));
+                    spans.push(((middle, middle_time), (b, b_time)));
+                }
+            }
+            found.sort_unstable_by_key(|(minute, _)| *minute);
+            refined.extend(found);
+        }
+        refined.extend(samples.last().copied());
+
+        let (departures, durations) : (Vec<u32>, Vec<Cost>) = refined.into_iter()
+            .filter_map(|(minute, time)| time.map(|time| (minute, time)))
+            .unzip();
+        Self {
+            departures : departures.into_boxed_slice(),
+            durations : durations.into_boxed_slice()
+        }
+    }
+
+    /// The sampled departure with the shortest travel time, in minutes since the Unix epoch, together with that travel
+    /// time. Travel times are linear between samples, so no departure in between is quicker. Ties go to the earliest.
+    pub fn fastest(&self) -> Option<(u32, Cost)> {
+        self.departures.iter().copied().zip(self.durations.iter().copied()).min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// The latest departure, in minutes since the Unix epoch, that still arrives by `arrival`, also in minutes since
+    /// the Unix epoch. Waiting never pays off under a FIFO travel time, so arrivals only grow with the departure and
+    /// the answer lies between the last sample arriving in time and the one after it.
+    pub fn latest_departure(&self, arrival : f64) -> Option<f64> {
+        let arrival_at = |position : usize| self.departures[position] as f64 + self.durations[position] as f64 * 60.0;
+        let position = (0..self.departures.len()).rev().find(|position| arrival_at(*position) <= arrival)?;
+        if position + 1 == self.departures.len() {
+            return Some(self.departures[position] as f64);
+        }
+        let (from, to) = (arrival_at(position), arrival_at(position + 1));
+        let (start, finish) = (self.departures[position] as f64, self.departures[position + 1] as f64);
+        if to <= from {
+            return Some(finish);
+        }
+        Some(start + (finish - start) * ((arrival - from) / (to - from)).min(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use crate::objects::load_shedding::OutageInterval;
+    use crate::objects::pathing::node_type::{NodeType, SearchMethod};
+    use crate::objects::pathing::test_graph::{grid, same_cost};
+
+    #[test]
+    fn profile_follows_the_travel_time_through_an_outage() {
+        let nodes = grid(3, 1, 0);
+        let outage_start = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (outage_start.timestamp() / 60) as u32;
+        let traffic_light = nodes.get_slice()[1].get_mut();
+        traffic_light.node_type = NodeType::AtTrafficLight;
+        traffic_light.outages = Box::new([OutageInterval { start : minute, end : minute + 120 }]);
+        let mut solver = Solver::new(nodes.get_slice(), 0, 2, 100_000, SearchMethod::FASTEST);
+        let (earliest, latest) = (outage_start - TimeDelta::minutes(31), outage_start + TimeDelta::minutes(180));
+        let profile = Profile::new(&mut solver, 0, 2, earliest, latest);
+        assert!(profile.departures.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(profile.departures.first(), Some(&(minute - 31)));
+        assert_eq!(profile.departures.last(), Some(&(minute + 180)));
+        assert!(profile.departures.len() < 60, "{} samples", profile.departures.len());
+        for departure in minute - 31..=minute + 180 {
+            let position = profile.departures.partition_point(|sample| *sample <= departure) - 1;
+            let mut duration = profile.durations[position];
+            if let Some(next) = profile.departures.get(position + 1) {
+                let fraction = (departure - profile.departures[position]) as Cost / (next - profile.departures[position]) as Cost;
+                duration += (profile.durations[position + 1] - duration) * fraction;
+            }
+            let expected = solver.travel_time(0, 2, DateTime::from_timestamp(departure as i64 * 60, 0).unwrap()).unwrap();
+            assert!((duration - expected).abs() <= 2.0 * TOLERANCE, "departing at {departure}: {duration} against {expected}");
+        }
+        let (fastest, duration) = profile.fastest().unwrap();
+        assert!(fastest < minute || fastest >= minute + 120);
+        assert!(same_cost(Some(duration), solver.travel_time(0, 2, latest)));
+    }
+
+    #[test]
+    fn unreachable_departures_are_left_out() {
+        let nodes = grid(2, 1, 1);
+        let mut solver = Solver::new(nodes.get_slice(), 0, 2, 100_000, SearchMethod::FASTEST);
+        let departure = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let profile = Profile::new(&mut solver, 0, 2, departure, departure + TimeDelta::minutes(60));
+        assert!(profile.departures.is_empty() && profile.durations.is_empty());
+        assert_eq!(profile.fastest(), None);
+        assert_eq!(profile.latest_departure(1e9), None);
+    }
+
+    #[test]
+    fn latest_departure_interpolates_between_samples() {
+        // Leaving at minute 0 arrives at 60, leaving at 60 arrives at 90 and leaving at 120 arrives at 150.
+        let profile = Profile {
+            departures : Box::new([0, 60, 120]),
+            durations : Box::new([1.0, 0.5, 0.5])
+        };
+        assert_eq!(profile.latest_departure(59.0), None);
+        assert_eq!(profile.latest_departure(60.0), Some(0.0));
+        assert_eq!(profile.latest_departure(75.0), Some(30.0));
+        assert_eq!(profile.latest_departure(120.0), Some(90.0));
+        assert_eq!(profile.latest_departure(1000.0), Some(120.0));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::new_slice;
+use crate::objects::pathing::connection::{Connection, RoadClass};
+use crate::objects::pathing::node::Node;
+use crate::objects::util::super_cell::SuperCell;
+use crate::types::Index;
+
+/// Incoming connections of every node, stored contiguously. The `index` of each stored `Connection` is the node the
+/// connection leaves from, while everything else is copied from the original outgoing connection.
+pub struct ReverseGraph {
+    offsets : Box<[u32]>,
+    connections : Box<[Connection]>
+}
+
+impl ReverseGraph {
+    pub fn new(nodes : &[SuperCell<Node>]) -> Self {
+        let mut offsets = new_slice(0u32, nodes.len() + 1);
+        for cell in nodes {
+            for connection in cell.get().get_connections() {
+                offsets[connection.index as usize + 1] += 1;
+            }
+        }
+        for index in 1..offsets.len() {
+            offsets[index] += offsets[index - 1];
+        }
+        let mut fill = offsets.clone();
+        let mut connections = Vec::with_capacity(offsets[nodes.len()] as usize);
+        connections.resize(offsets[nodes.len()] as usize, Connection { index: 0, cost: 0.0, speed: 0, road_class: RoadClass::Unclassified, one_way: false });
+        for cell in nodes {
+            let node = cell.get();
+            for connection in node.get_connections() {
+                let slot = &mut fill[connection.index as usize];
+                connections[*slot as usize] = Connection {
+                    index: node.index,
+                    cost: connection.cost,
+                    speed: connection.speed,
+                    road_class: connection.road_class,
+                    one_way: connection.one_way
+                };
+                *slot += 1;
+            }
+        }
+        Self {
+            offsets,
+            connections: connections.into_boxed_slice()
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_connections(&self, index : Index) -> &[Connection] {
+        &self.connections[self.offsets[index as usize] as usize..self.offsets[index as usize + 1] as usize]
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
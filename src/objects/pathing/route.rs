@@ -0,0 +1,84 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::objects::pathing::path_segment::PathSegment;
+use crate::objects::pathing::solver::Solver;
+use crate::types::{Cost, Index};
+
+/// A path through several waypoints in a fixed order, one leg between every two consecutive waypoints. Unlike the
+/// paths of `Solver`, the path runs from the first waypoint to the last, and every node where one leg ends and the
+/// next begins appears once.
+pub struct Route {
+    pub path : Box<[Index]>,
+    pub time : Cost,
+    pub distance : Cost,
+    /// Segments of every leg, with arrival times counted from the departure at the first waypoint.
+    pub segments : Box<[PathSegment]>,
+    /// Position in `path` of the waypoint every leg ends at.
+    pub leg_ends : Box<[usize]>
+}
+
+impl Route {
+    /// Searches every leg with the solver's current algorithm, departing each waypoint at the time the previous leg
+    /// arrives there, so that the outages along later legs are those at the time they are driven. A leg between a
+    /// repeated waypoint is empty and costs nothing. `None` when fewer than two waypoints are given or any leg has no
+    /// path.
+    pub fn new(solver : &mut Solver, waypoints : &[Index], departure : DateTime<Utc>) -> Option<Self> {
+        if waypoints.len() < 2 {
+            return None;
+        }
+        let mut path = vec![waypoints[0]];
+        let mut segments = Vec::new();
+        let mut leg_ends = Vec::with_capacity(waypoints.len() - 1);
+        let mut time = 0.0;
+        let mut distance = 0.0;
+        for leg in waypoints.windows(2) {
+            let leg_departure = departure + TimeDelta::milliseconds((time as f64 * 3_600_000.0) as i64);
+            solver.travel_time(leg[0], leg[1], leg_departure)?;
+            let (leg_path, leg_time, leg_distance) = solver.get_path_as_indices().as_ref()?;
+            path.extend(leg_path.iter().rev().skip(1));
+            segments.extend(solver.get_path_segments().iter().map(|segment| PathSegment {
+                arrival : segment.arrival + time,
+                ..*segment
+            }));
+            leg_ends.push(path.len() - 1);
+            time += leg_time;
+            distance += leg_distance;
+        }
+        Some(Self {
+            path : path.into_boxed_slice(),
+            time,
+            distance,
+            segments : segments.into_boxed_slice(),
+            leg_ends : leg_ends.into_boxed_slice()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::pathing::node_type::{SearchAlgorithm, SearchMethod};
+    use crate::objects::pathing::reverse_graph::ReverseGraph;
+    use crate::objects::pathing::test_graph::{grid, same_cost};
+
+    #[test]
+    fn legs_to_the_same_node_cost_nothing() {
+        let nodes = grid(4, 4, 0);
+        let reverse_graph = ReverseGraph::new(nodes.get_slice());
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        solver.set_reverse_graph(&reverse_graph);
+        let departure = Utc::now();
+        for algorithm in [SearchAlgorithm::DIJKSTRA, SearchAlgorithm::ASTAR, SearchAlgorithm::BIDIRECTIONAL, SearchAlgorithm::CONTRACTION,
+                          SearchAlgorithm::CUSTOMIZABLE, SearchAlgorithm::LANDMARKS, SearchAlgorithm::EDGES] {
+            solver.search_algorithm = algorithm;
+            assert_eq!(solver.travel_time(5, 5, departure), Some(0.0), "{algorithm:?}");
+            assert!(solver.get_path_segments().is_empty());
+            let direct = Route::new(&mut solver, &[0, 15], departure).expect("No route");
+            let route = Route::new(&mut solver, &[0, 0, 15, 15], departure).expect("No route through repeated waypoints");
+            assert_eq!(route.path, direct.path);
+            assert!(same_cost(Some(route.time), Some(direct.time)));
+            assert_eq!(route.segments.len(), direct.segments.len());
+            assert_eq!(*route.leg_ends, [0, direct.path.len() - 1, direct.path.len() - 1]);
+        }
+    }
+}
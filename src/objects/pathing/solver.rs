@@ -1,12 +1,23 @@
+use crate::objects::load_shedding::OutageClock;
 use crate::objects::pathing::connection::Connection;
+use crate::objects::pathing::cost_model::{CostModel, SpeedSource};
+use crate::objects::pathing::contraction_hierarchy::{ContractionHierarchy, HierarchyQuery};
+use crate::objects::pathing::customizable_hierarchy::{CustomizableHierarchy, CustomizableQuery};
+use crate::objects::pathing::landmarks::Landmarks;
 use crate::objects::pathing::node::Node;
-use crate::objects::pathing::node_type::{NodeType, SearchMethod};
+use crate::objects::pathing::node_type::{NodeType, SearchAlgorithm, SearchMethod};
+use crate::objects::pathing::path_segment::PathSegment;
+use crate::objects::pathing::profile::Profile;
+use crate::objects::pathing::reverse_graph::ReverseGraph;
+use crate::objects::pathing::turns::{EdgeSearch, TurnGraph};
 use crate::objects::util::parallel_list::ParallelList;
 use crate::objects::util::super_cell::SuperCell;
 use crate::types::{Cost, Flag, Index, Pos};
-use chrono::{Timelike, Utc};
+use crate::distance;
+use chrono::{DateTime, NaiveDateTime, Timelike, Utc};
 use radix_heap::RadixHeapMap;
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::simd::Simd;
 
 const HOUR_TO_MIN : f64 = 60f64;
@@ -18,6 +29,15 @@ const MAX_TIME_S : u32 = MAX_TIME_MIN*60;
 const MAX_TIME_MS : u32 = MAX_TIME_S*1000;
 const MAX_TIME : u32 = MAX_TIME_S;
 const CONVERSION_FACTOR : Cost = HOUR_TO_SEC as Cost;
+const HOURS_PER_DAY : u32 = 24;
+/// Hours South African local time is ahead of UTC, the default for `Solver::utc_offset`.
+pub const SOUTH_AFRICA_UTC_OFFSET : Cost = 2.0;
+/// Fraction of its weight added to a connection every time an alternative route uses it.
+const ALTERNATIVE_PENALTY : Cost = 0.4;
+/// Searches allowed per requested alternative before giving up on finding one different enough.
+const ALTERNATIVE_ATTEMPTS : usize = 5;
+/// Relative slack on the bound of the bidirectional search, for lower bounds added up in another order than the costs.
+const BIDIRECTIONAL_SLACK : Cost = 1e-5;
 
 pub struct Solver<'solver> {
     start_node : Index,
@@ -26,14 +46,68 @@ pub struct Solver<'solver> {
     costs : ParallelList<Cost>,
     previous_indices: ParallelList<Index>,
     previous_distances : ParallelList<Cost>,
+    /// Weight of the connection each node was last reached through, and the part of it driving with the lights on.
+    previous_weights : ParallelList<Cost>,
+    previous_base_times : ParallelList<Cost>,
     connection_lens : ParallelList<u16>,
     path : Option<(Box<[Index]>, Cost, Cost)>,
+    segments : Box<[PathSegment]>,
     heap : RadixHeapMap<u32, Index>,
     backup_heap : RadixHeapMap<u32, Index>,
     current_iteration : u32,
     max_iterations : u32,
+    settled_nodes : u32,
+    /// Bounds of the graph for the A* heuristic, worked out on the first A* search unless set before.
+    network_bounds : Option<NetworkBounds>,
+    backward_costs : ParallelList<Cost>,
+    next_indices : ParallelList<Index>,
+    next_distances : ParallelList<Cost>,
+    backward_connection_lens : ParallelList<u16>,
+    backward_heap : RadixHeapMap<u32, Index>,
+    meeting_node : Index,
+    meeting_cost : Cost,
     pub search_method : SearchMethod,
-    nodes: &'solver [SuperCell<Node>]
+    /// How connections are weighed, the model of `search_method` unless a custom one is set.
+    pub cost_model : CostModel,
+    pub search_algorithm : SearchAlgorithm,
+    pub utc_offset : Cost,
+    departure_time : Cost,
+    departure_midnight : f64,
+    nodes: &'solver [SuperCell<Node>],
+    reverse_graph : Option<&'solver ReverseGraph>,
+    contraction_hierarchy : Option<&'solver ContractionHierarchy>,
+    hierarchy_query : Option<HierarchyQuery>,
+    customizable_hierarchy : Option<&'solver SuperCell<CustomizableHierarchy>>,
+    customizable_query : Option<CustomizableQuery>,
+    landmarks : Option<&'solver Landmarks>,
+    turn_graph : Option<&'solver TurnGraph>,
+    edge_search : Option<EdgeSearch>
+}
+
+/// The highest connection speed in the network, and the smallest ratio between a connection's cost and the straight
+/// line `distance` it spans, so that the A* heuristic never overestimates the remaining cost. Takes a pass over the
+/// whole graph, so it is worked out once when the graph loads and handed to every solver.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct NetworkBounds {
+    pub max_speed : Cost,
+    pub cost_per_metre : Cost
+}
+
+impl NetworkBounds {
+    pub fn new(nodes : &[SuperCell<Node>]) -> Self {
+        let (max_speed, cost_per_metre) = nodes.par_iter().map(|cell| {
+            let node = cell.get();
+            node.get_connections().iter().fold((0 as Cost, Cost::MAX), |(speed, ratio), connection| {
+                let straight_line = distance(&node.position, &nodes[connection.index as usize].get().position) as Cost;
+                let ratio = if straight_line > 0.0 { ratio.min(connection.cost / straight_line) } else { ratio };
+                (speed.max(connection.speed as Cost), ratio)
+            })
+        }).reduce(|| (0 as Cost, Cost::MAX), |left, right| (left.0.max(right.0), left.1.min(right.1)));
+        Self {
+            max_speed,
+            cost_per_metre : if cost_per_metre == Cost::MAX { 0.0 } else { cost_per_metre }
+        }
+    }
 }
 
 
@@ -43,6 +117,7 @@ impl <'solver> Solver<'solver>  {
              heap : RadixHeapMap::new(),
              backup_heap : RadixHeapMap::new(),
              path : None,
+             segments : Box::new([]),
              start_node : start_node_index as Index,
              end_node : end_node_index as Index,
              current_iteration : 0u32,
@@ -50,51 +125,116 @@ impl <'solver> Solver<'solver>  {
              costs: ParallelList::new(nodes.len()),
              previous_indices: ParallelList::new(nodes.len()),
              previous_distances: ParallelList::new(nodes.len()),
+             previous_weights: ParallelList::new(nodes.len()),
+             previous_base_times: ParallelList::new(nodes.len()),
              connection_lens: ParallelList::new(nodes.len()),
+             backward_costs: ParallelList::new(0),
+             next_indices: ParallelList::new(0),
+             next_distances: ParallelList::new(0),
+             backward_connection_lens: ParallelList::new(0),
+             backward_heap : RadixHeapMap::new(),
+             meeting_node : Index::MAX,
+             meeting_cost : Cost::MAX,
              max_iterations,
+             settled_nodes : 0u32,
+             network_bounds : None,
              nodes,
-             search_method
+             reverse_graph : None,
+             contraction_hierarchy : None,
+             hierarchy_query : None,
+             customizable_hierarchy : None,
+             customizable_query : None,
+             landmarks : None,
+             turn_graph : None,
+             edge_search : None,
+             search_method,
+             cost_model : search_method.cost_model(),
+             search_algorithm : SearchAlgorithm::DIJKSTRA,
+             utc_offset : SOUTH_AFRICA_UTC_OFFSET,
+             departure_time : 0.0,
+             departure_midnight : 0.0
          };
+        new.set_departure_time(Utc::now());
         new.start();
         new
     }
 
     #[inline]
-    fn calculate_weight(&self, connection : &Connection, node_type: NodeType, flag: Flag, time_offset : Cost) -> Cost {
-        
-        match self.search_method {
-            SearchMethod::FASTEST => {
-                let connection_cost = connection.cost / (connection.speed as Cost);
-                match node_type {
-                    NodeType::Normal => connection_cost,
-                    NodeType::NearTrafficLight => connection_cost + connection_cost * (3 as Cost) * Self::is_load_shedding(flag, time_offset),
-                    NodeType::AtTrafficLight => connection_cost + connection_cost * (5 as Cost) * Self::is_load_shedding(flag, time_offset)
+    fn calculate_weight(&self, connection : &Connection, node : &Node, time_offset : Cost) -> Cost {
+        let wait = match node.node_type {
+            NodeType::Normal => 0.0,
+            _ => self.outage_clock().wait(node, time_offset)
+        };
+        self.cost_model.weight(connection, node.node_type, wait)
+    }
+
+    /// Switches to `search_method` together with its cost model.
+    pub fn set_search_method(&mut self, search_method : SearchMethod) {
+        self.search_method = search_method;
+        self.cost_model = search_method.cost_model();
+    }
+
+    /// Answers whether a node's lights are out at the times this solver's searches ask about.
+    #[inline]
+    pub fn outage_clock(&self) -> OutageClock {
+        OutageClock {
+            midnight : self.departure_midnight,
+            departure : self.departure_time
+        }
+    }
+    
+    /// Lower bound of the remaining cost from `index` to the end node. Always zero for plain Dijkstra.
+    #[inline]
+    fn heuristic(&self, index : Index) -> Cost {
+        match self.search_algorithm {
+            SearchAlgorithm::DIJKSTRA | SearchAlgorithm::BIDIRECTIONAL | SearchAlgorithm::CONTRACTION | SearchAlgorithm::CUSTOMIZABLE | SearchAlgorithm::EDGES => 0.0,
+            SearchAlgorithm::ASTAR => {
+                let Some(bounds) = self.network_bounds else {
+                    return 0.0;
+                };
+                let end_position = &self.nodes[self.end_node as usize].get().position;
+                let straight_line = distance(&self.nodes[index as usize].get().position, end_position) as Cost * bounds.cost_per_metre;
+                match self.cost_model.speed {
+                    SpeedSource::Fixed(speed) => straight_line / speed,
+                    SpeedSource::Connection if bounds.max_speed > 0.0 => straight_line / bounds.max_speed,
+                    SpeedSource::Connection => 0.0
                 }
             },
-            SearchMethod::SHORTEST => connection.cost / 60.0,
-            SearchMethod::AVOID => {
-                let connection_cost = connection.cost / (connection.speed as Cost);
-                match node_type {
-                    NodeType::Normal => connection_cost,
-                    NodeType::NearTrafficLight => connection_cost + connection_cost * (100 as Cost) * Self::is_load_shedding(flag, time_offset),
-                    NodeType::AtTrafficLight => connection_cost + connection_cost * (200 as Cost) * Self::is_load_shedding(flag, time_offset)
-                }
+            SearchAlgorithm::LANDMARKS => match self.landmarks {
+                Some(landmarks) if landmarks.search_method.cost_model() == self.cost_model => landmarks.lower_bound(index, self.end_node),
+                _ => 0.0
             }
         }
     }
-    
+
+    /// Radix heap key of a cost, larger for cheaper costs. Costs of `MAX_TIME` seconds and over all share the key 0, so
+    /// that searches that far still run, in no particular order past that point.
+    #[inline(always)]
+    pub fn to_key(estimate : Cost) -> u32 {
+        MAX_TIME.saturating_sub((estimate*CONVERSION_FACTOR) as u32)
+    }
+
+    #[inline(always)]
+    pub fn get_settled_nodes(&self) -> u32 {
+        self.settled_nodes
+    }
+
     #[inline(always)]
     pub fn get_connection_len(&self, index: Index) -> u16 {
         self.connection_lens[index as usize]
     }
 
+    /// Reaches `index_source` at `new_cost` through `connection` of weight `weight` if that is cheaper than before,
+    /// recording how it was reached for `backtrack`.
     #[inline(always)]
-    pub fn check_updated_and_save(&mut self, index_source : Index, new_cost : Cost, connection_cost: Cost, previous : usize, length : u16) -> bool {
+    pub fn check_updated_and_save(&mut self, index_source : Index, new_cost : Cost, connection : &Connection, weight : Cost, previous : usize, length : u16) -> bool {
         if self.costs[index_source as usize] > new_cost {
             self.costs[index_source as usize] = new_cost;
             self.previous_indices[index_source as usize] = previous as u32;
             self.connection_lens[index_source as usize] = length;
-            self.previous_distances[index_source as usize] = connection_cost;
+            self.previous_distances[index_source as usize] = connection.cost;
+            self.previous_weights[index_source as usize] = weight;
+            self.previous_base_times[index_source as usize] = self.cost_model.free_flow(connection);
             return true;
         }
         false
@@ -123,24 +263,94 @@ impl <'solver> Solver<'solver>  {
     }
 
     #[inline(always)]
-    pub fn get_nodes(&'solver self) -> &'solver [SuperCell<Node>] {
+    pub fn get_nodes(&self) -> &'solver [SuperCell<Node>] {
         self.nodes
     }
 
+    /// Gives the solver the incoming connections of its nodes, which the bidirectional search walks backwards from
+    /// the end node.
+    pub fn set_reverse_graph(&mut self, reverse_graph : &'solver ReverseGraph) {
+        self.reverse_graph = Some(reverse_graph);
+    }
+
+    /// Gives the solver the bounds of its graph for the A* heuristic, so that it does not work them out itself.
+    pub fn set_network_bounds(&mut self, network_bounds : NetworkBounds) {
+        self.network_bounds = Some(network_bounds);
+    }
+
+    /// Lets `SearchAlgorithm::CONTRACTION` answer queries from `contraction_hierarchy`. Searches for any other
+    /// `SearchMethod` than the one the hierarchy was built for fall back to Dijkstra.
+    pub fn set_contraction_hierarchy(&mut self, contraction_hierarchy : &'solver ContractionHierarchy) {
+        self.contraction_hierarchy = Some(contraction_hierarchy);
+    }
+
+    #[inline(always)]
+    fn uses_contraction_hierarchy(&self) -> bool {
+        self.search_algorithm == SearchAlgorithm::CONTRACTION && self.contraction_hierarchy.is_some_and(|hierarchy| hierarchy.search_method.cost_model() == self.cost_model)
+    }
+
+    /// Lets `SearchAlgorithm::CUSTOMIZABLE` answer queries from `customizable_hierarchy`, customizing it again when a
+    /// search departs in another outage slot than it was customized for. Other `SearchMethod`s fall back to Dijkstra.
+    pub fn set_customizable_hierarchy(&mut self, customizable_hierarchy : &'solver SuperCell<CustomizableHierarchy>) {
+        self.customizable_hierarchy = Some(customizable_hierarchy);
+    }
+
+    /// Lets `SearchAlgorithm::LANDMARKS` use the ALT tables of `landmarks` as its heuristic. Searches for any other
+    /// `SearchMethod` than the one the tables were built for run as plain Dijkstra.
+    pub fn set_landmarks(&mut self, landmarks : &'solver Landmarks) {
+        self.landmarks = Some(landmarks);
+    }
+
+    /// Lets `SearchAlgorithm::EDGES` search the edges of `turn_graph`, obeying its turn restrictions and costs.
+    /// Without one, edge based searches run as plain Dijkstra.
+    pub fn set_turn_graph(&mut self, turn_graph : &'solver TurnGraph) {
+        self.turn_graph = Some(turn_graph);
+    }
+
+    #[inline(always)]
+    fn uses_customizable_hierarchy(&self) -> bool {
+        self.search_algorithm == SearchAlgorithm::CUSTOMIZABLE && self.customizable_hierarchy.is_some_and(|hierarchy| hierarchy.get().search_method.cost_model() == self.cost_model)
+    }
+
     #[inline(always)]
     pub fn update_search_speed(&mut self, new_speed : u32) {
         self.max_iterations = new_speed;
     }
 
-    pub fn update_search(&mut self, start_node_index : Index, end_node_index : Index) {
+    pub fn update_search(&mut self, start_node_index : Index, end_node_index : Index, departure_time : DateTime<Utc>) {
         self.start_node = start_node_index;
         self.end_node = end_node_index;
+        self.set_departure_time(departure_time);
         self.reset();
+        if start_node_index == end_node_index {
+            // Already there, so the search is done before it starts with a path that costs nothing.
+            self.path = Some((Box::new([start_node_index]), 0.0, 0.0));
+        }
         println!("Finding search between {start_node_index} to {end_node_index}");
     }
+
+    /// Departure time of the following searches, converted to local time with `utc_offset`.
+    #[inline]
+    pub fn set_departure_time(&mut self, departure_time : DateTime<Utc>) {
+        let clock = OutageClock::new(departure_time, self.utc_offset);
+        self.departure_time = clock.departure;
+        self.departure_midnight = clock.midnight;
+    }
+
+    /// Local departure time of the searches, in hours since midnight.
+    #[inline(always)]
+    pub fn get_departure_time(&self) -> Cost {
+        self.departure_time
+    }
     #[inline(always)]
     pub fn fully_searched(&self) -> bool {
-        self.heap.is_empty() || self.has_visited(self.end_node)
+        match self.search_algorithm {
+            SearchAlgorithm::BIDIRECTIONAL => self.path.is_some() || (self.heap.is_empty() && self.backward_heap.is_empty()),
+            // These search in one go, or fall back to Dijkstra, and never pop from `backward_heap`, which `reset` may
+            // have refilled after an earlier bidirectional search.
+            SearchAlgorithm::CONTRACTION | SearchAlgorithm::CUSTOMIZABLE | SearchAlgorithm::EDGES => self.path.is_some() || self.heap.is_empty(),
+            _ => self.heap.is_empty() || self.has_visited(self.end_node)
+        }
     }
 
     fn merge(&mut self) {
@@ -159,10 +369,41 @@ impl <'solver> Solver<'solver>  {
         }
     }
 
+    /// Local date and time at `time`, for a time zone `utc_offset` hours ahead of UTC.
+    #[inline]
+    pub fn local_time(time : DateTime<Utc>, utc_offset : Cost) -> NaiveDateTime {
+        (time + chrono::Duration::seconds((utc_offset as f64 * HOUR_TO_SEC) as i64)).naive_utc()
+    }
+
+    /// Hours since local midnight at `time`, for a time zone `utc_offset` hours ahead of UTC. Adding the cost of a path
+    /// so far gives the time `OutageClock::wait` is asked about.
+    #[inline]
+    pub fn time_of_day(time : DateTime<Utc>, utc_offset : Cost) -> Cost {
+        (Self::local_time(time, utc_offset).time().num_seconds_from_midnight() as f64 / HOUR_TO_SEC) as Cost
+    }
+
+    /// Whether the flag has load shedding during the hour of the day `current_cost_time` falls in, where bit `n` of the
+    /// flag is the hour starting at `n`:00. Times past midnight wrap around to the next day.
     pub const fn is_load_shedding(flag : u32, current_cost_time : Cost) -> Cost {
-        (flag << (31 - current_cost_time as u32) >> 31) as Cost
+        let hour = current_cost_time as u32 % HOURS_PER_DAY;
+        (flag << (31 - hour) >> 31) as Cost
     }
     
+    /// Hours from `time` until the end of the run of load shedding hours in `flag` that `time` falls in, or 0 when it
+    /// falls in none. Runs wrap around midnight, but never last longer than a day.
+    pub fn flag_wait(flag : Flag, time : Cost) -> Cost {
+        let hour = time.floor();
+        let mut hours = 0;
+        while hours < HOURS_PER_DAY && Self::is_load_shedding(flag, hour + hours as Cost) == 1.0 {
+            hours += 1;
+        }
+        if hours == 0 {
+            0.0
+        } else {
+            hour + hours as Cost - time
+        }
+    }
+
     fn reset_index(&mut self, index: Index) {
         self.costs[index as usize] = Cost::MAX;
         self.previous_indices[index as usize] = u32::MAX;
@@ -171,30 +412,39 @@ impl <'solver> Solver<'solver>  {
 
     fn compute_radix(&mut self) {
         let end_node_index = self.end_node;
-        let time_in_hour = (Utc::now().time().minute() as f64/60f64) as Cost;
+        let time_in_hour = self.departure_time;
+        let is_a_star = matches!(self.search_algorithm, SearchAlgorithm::ASTAR | SearchAlgorithm::LANDMARKS);
         while !self.heap.is_empty() && self.current_iteration < self.max_iterations {
             self.current_iteration += 1;
             self.total_iterations += 1;
             let pop = self.heap.pop().expect("Heap was not empty, but had nothing to pop.");
             let current_node_index = pop.1;
             let local_cost = self.costs[current_node_index as usize];
-            if self.is_lower_cost(self.end_node, local_cost) {
+            let estimate = local_cost + self.heuristic(current_node_index);
+            if pop.0 < Self::to_key(estimate) {
+                // Stale entry, the node was pushed again with a lower cost.
                 continue;
             }
+            if self.is_lower_cost(self.end_node, estimate) {
+                if is_a_star && pop.0 < Self::to_key(self.costs[end_node_index as usize]) {
+                    // Keys are whole seconds, so only once the popped key is past the end node's is every remaining
+                    // estimate known to be larger, and the end node settled.
+                    self.heap.clear();
+                    break;
+                }
+                continue;
+            }
+            self.settled_nodes += 1;
             let connected_node = self.nodes[current_node_index as usize].get();
-            let node_type = connected_node.node_type;
-            let flag = connected_node.flag;
             let pop_cost = pop.0;
             let new_node_length = self.get_connection_len(current_node_index) + 1;
             let time_offset_cost = time_in_hour + local_cost;
             for connection in connected_node.get_connections() {
-                let connection_distance = connection.cost;
-                let connection_cost = self.calculate_weight(connection, node_type, flag, time_offset_cost);
+                let connection_cost = self.calculate_weight(connection, connected_node, time_offset_cost);
                 let connection_index = connection.index;
                 let new_local_cost = local_cost + connection_cost;
-                if self.check_updated_and_save(connection_index, new_local_cost, connection_distance, current_node_index as usize, new_node_length) && connection_index != end_node_index {
-                    let tmp = (new_local_cost*CONVERSION_FACTOR) as u32;
-                    let push_cost = MAX_TIME - tmp;
+                if self.check_updated_and_save(connection_index, new_local_cost, connection, connection_cost, current_node_index as usize, new_node_length) && connection_index != end_node_index {
+                    let push_cost = Self::to_key(new_local_cost + self.heuristic(connection_index));
                     if push_cost <= pop_cost {
                         self.heap.push(push_cost, connection.index);
                     } else {
@@ -205,6 +455,397 @@ impl <'solver> Solver<'solver>  {
         }
     }
     
+    /// Settles every node reachable from `source` with free flow weights, the lowest cost a connection can have at
+    /// any time, following incoming connections instead when `backward` is set. The landmark tables are built from
+    /// these costs.
+    pub fn free_flow_costs(&mut self, source : Index, backward : bool) -> Box<[Cost]> {
+        let reverse_graph = if backward {
+            Some(self.reverse_graph.expect("Backward costs require a reverse graph, see Solver::set_reverse_graph"))
+        } else {
+            None
+        };
+        let nodes = self.nodes;
+        self.start_node = source;
+        self.end_node = source;
+        self.reset();
+        while let Some((key, index)) = self.heap.pop() {
+            let local_cost = self.costs[index as usize];
+            if key < Self::to_key(local_cost) {
+                continue;
+            }
+            self.settled_nodes += 1;
+            let connections = match reverse_graph {
+                Some(reverse_graph) => reverse_graph.get_connections(index),
+                None => nodes[index as usize].get().get_connections()
+            };
+            for connection in connections {
+                let new_cost = local_cost + self.cost_model.weight(connection, NodeType::Normal, 0.0);
+                if new_cost < self.costs[connection.index as usize] {
+                    self.costs[connection.index as usize] = new_cost;
+                    self.heap.push(Self::to_key(new_cost), connection.index);
+                }
+            }
+        }
+        self.costs.as_slice().into()
+    }
+
+    /// Costs from `source` to each of `targets`, in the same order, with `Cost::MAX` for targets that cannot be reached.
+    /// The search stops as soon as the last target is settled.
+    pub fn one_to_many(&mut self, source : Index, targets : &[Index]) -> Box<[Cost]> {
+        let mut remaining : HashSet<Index> = targets.iter().copied().collect();
+        let time_in_hour = self.departure_time;
+        let nodes = self.nodes;
+        self.start_node = source;
+        self.end_node = source;
+        self.reset();
+        while let Some((key, index)) = self.heap.pop() {
+            let local_cost = self.costs[index as usize];
+            if key < Self::to_key(local_cost) {
+                continue;
+            }
+            self.settled_nodes += 1;
+            if remaining.remove(&index) && remaining.is_empty() {
+                break;
+            }
+            let node = nodes[index as usize].get();
+            for connection in node.get_connections() {
+                let new_cost = local_cost + self.calculate_weight(connection, node, time_in_hour + local_cost);
+                if new_cost < self.costs[connection.index as usize] {
+                    self.costs[connection.index as usize] = new_cost;
+                    self.heap.push(Self::to_key(new_cost), connection.index);
+                }
+            }
+        }
+        self.heap.clear();
+        targets.iter().map(|target| self.costs[*target as usize]).collect()
+    }
+
+    /// Settles every node that can be reached from `source` within `cutoff`, given in the units of `CONVERSION_FACTOR`
+    /// (seconds), under the current load shedding. Returns the settled nodes in the order they were settled. Cutoffs
+    /// past `MAX_TIME` are cut down to it, as costs beyond it no longer order in the heap.
+    pub fn bounded_search(&mut self, source : Index, cutoff : u32) -> Box<[Index]> {
+        let cutoff = cutoff.min(MAX_TIME);
+        let time_in_hour = self.departure_time;
+        let nodes = self.nodes;
+        let mut settled = Vec::new();
+        self.start_node = source;
+        self.end_node = source;
+        self.reset();
+        while let Some((key, index)) = self.heap.pop() {
+            let local_cost = self.costs[index as usize];
+            if key < Self::to_key(local_cost) {
+                continue;
+            }
+            self.settled_nodes += 1;
+            settled.push(index);
+            let node = nodes[index as usize].get();
+            for connection in node.get_connections() {
+                let new_cost = local_cost + self.calculate_weight(connection, node, time_in_hour + local_cost);
+                if new_cost < self.costs[connection.index as usize] && new_cost * CONVERSION_FACTOR <= cutoff as Cost {
+                    self.costs[connection.index as usize] = new_cost;
+                    self.heap.push(Self::to_key(new_cost), connection.index);
+                }
+            }
+        }
+        self.heap.clear();
+        settled.into_boxed_slice()
+    }
+
+    #[inline(always)]
+    fn has_backward_state(&self) -> bool {
+        self.backward_costs.get_size() == self.nodes.len()
+    }
+
+    fn reset_backward(&mut self) {
+        if !self.has_backward_state() {
+            let size = self.nodes.len();
+            self.backward_costs = ParallelList::new(size);
+            self.next_indices = ParallelList::new(size);
+            self.next_distances = ParallelList::new(size);
+            self.backward_connection_lens = ParallelList::new(size);
+        }
+        self.backward_heap.clear();
+        self.backward_costs.as_slice_mut().par_iter_mut().for_each(|x| {*x = Cost::MAX});
+        self.next_indices.as_slice_mut().par_iter_mut().for_each(|x| {*x = u32::MAX});
+        self.next_distances.as_slice_mut().par_iter_mut().for_each(|x| {*x = 0.0});
+        self.backward_connection_lens.as_slice_mut().par_iter_mut().for_each(|x| {*x = 0u16});
+        self.backward_heap.push(MAX_TIME, self.end_node);
+        self.backward_costs[self.end_node as usize] = 0f64 as Cost;
+        if self.start_node == self.end_node {
+            self.meeting_node = self.start_node;
+            self.meeting_cost = 0.0;
+        }
+    }
+
+    /// Lower bound of the cost of every entry still waiting in `heap`.
+    #[inline(always)]
+    fn search_radius(heap : &RadixHeapMap<u32, Index>) -> Cost {
+        heap.top().map(|key| (MAX_TIME - key) as Cost / CONVERSION_FACTOR).unwrap_or(0.0)
+    }
+
+    /// Records the first node reached by both searches, with the cost of driving the path through it from the
+    /// departure, which every later step of the search is bounded by.
+    fn meet(&mut self, index : Index) {
+        self.meeting_node = index;
+        self.meeting_cost = self.backtrack_bidirectional(self.departure_time).2;
+    }
+
+    /// Largest lower bound to the end node a node on a path cheaper than the meeting path can have, with some slack for
+    /// the rounding of adding up the same weights in the other direction.
+    #[inline(always)]
+    fn meeting_bound(&self) -> Cost {
+        self.meeting_cost * (1.0 + BIDIRECTIONAL_SLACK)
+    }
+
+    /// Settles the next node of the time-dependent forward search. Once the searches have met, only nodes the backward
+    /// search settled within the meeting bound are expanded, as no cheaper path runs through any other.
+    fn step_forward(&mut self, time_in_hour : Cost) {
+        let pop = self.heap.pop().expect("Heap was not empty, but had nothing to pop.");
+        let current_node_index = pop.1;
+        let local_cost = self.costs[current_node_index as usize];
+        if pop.0 < Self::to_key(local_cost) || local_cost > self.meeting_cost {
+            return;
+        }
+        if self.meeting_cost != Cost::MAX && self.backward_costs[current_node_index as usize] > self.meeting_bound() {
+            return;
+        }
+        self.settled_nodes += 1;
+        let connected_node = self.nodes[current_node_index as usize].get();
+        let new_node_length = self.get_connection_len(current_node_index) + 1;
+        let time_offset_cost = time_in_hour + local_cost;
+        for connection in connected_node.get_connections() {
+            let connection_index = connection.index;
+            let weight = self.calculate_weight(connection, connected_node, time_offset_cost);
+            let new_local_cost = local_cost + weight;
+            if self.check_updated_and_save(connection_index, new_local_cost, connection, weight, current_node_index as usize, new_node_length) {
+                self.heap.push(Self::to_key(new_local_cost), connection_index);
+            }
+            if self.meeting_cost == Cost::MAX && self.backward_costs[connection_index as usize] != Cost::MAX {
+                self.meet(connection_index);
+            }
+        }
+    }
+
+    /// Settles the next node of the backward search, which weighs every connection with the lights on. Its costs are
+    /// lower bounds of the cost to the end node at any time of day, whatever the outages.
+    fn step_backward(&mut self) {
+        let reverse_graph = self.reverse_graph.expect("Bidirectional search requires a reverse graph, see Solver::set_reverse_graph");
+        let pop = self.backward_heap.pop().expect("Heap was not empty, but had nothing to pop.");
+        let current_node_index = pop.1;
+        let local_cost = self.backward_costs[current_node_index as usize];
+        if pop.0 < Self::to_key(local_cost) {
+            return;
+        }
+        self.settled_nodes += 1;
+        let new_node_length = self.backward_connection_lens[current_node_index as usize] + 1;
+        for connection in reverse_graph.get_connections(current_node_index) {
+            let source_index = connection.index;
+            let source_node = self.nodes[source_index as usize].get();
+            let new_local_cost = local_cost + self.cost_model.weight(connection, source_node.node_type, 0.0);
+            if self.backward_costs[source_index as usize] > new_local_cost {
+                self.backward_costs[source_index as usize] = new_local_cost;
+                self.next_indices[source_index as usize] = current_node_index;
+                self.next_distances[source_index as usize] = connection.cost;
+                self.backward_connection_lens[source_index as usize] = new_node_length;
+                self.backward_heap.push(Self::to_key(new_local_cost), source_index);
+            }
+            if self.meeting_cost == Cost::MAX && self.costs[source_index as usize] != Cost::MAX {
+                self.meet(source_index);
+            }
+        }
+    }
+
+    /// Searches from both ends at once, the forward search with the time-dependent weights and the backward search
+    /// with lower bounds, growing the side with the smaller radius until they meet. The cost of the path through the
+    /// meeting node bounds the optimum, so the backward search then settles every node within that bound of the end
+    /// node, and the forward search finishes over those nodes alone, finding the same cost as Dijkstra would.
+    fn compute_bidirectional(&mut self) {
+        if !self.has_backward_state() {
+            self.reset_backward();
+        }
+        let time_in_hour = self.departure_time;
+        let end_node_index = self.end_node;
+        while self.current_iteration < self.max_iterations {
+            let forward_radius = Self::search_radius(&self.heap);
+            let backward_radius = Self::search_radius(&self.backward_heap);
+            if self.meeting_cost == Cost::MAX {
+                if self.heap.is_empty() || self.backward_heap.is_empty() {
+                    self.heap.clear();
+                    self.backward_heap.clear();
+                    break;
+                }
+                if forward_radius <= backward_radius {
+                    self.step_forward(time_in_hour);
+                } else {
+                    self.step_backward();
+                }
+            } else if !self.backward_heap.is_empty() && backward_radius <= self.meeting_bound() {
+                self.step_backward();
+            } else if !self.heap.is_empty() && forward_radius < self.costs[end_node_index as usize] {
+                self.step_forward(time_in_hour);
+            } else {
+                self.heap.clear();
+                self.backward_heap.clear();
+                break;
+            }
+            self.current_iteration += 1;
+            self.total_iterations += 1;
+        }
+    }
+
+    fn find_connection(&self, from : Index, to : Index) -> Option<&Connection> {
+        self.nodes[from as usize].get().get_connections().iter()
+            .filter(|connection| connection.index == to)
+            .min_by(|left, right| self.cost_model.free_flow(left).total_cmp(&self.cost_model.free_flow(right)))
+    }
+
+    /// Joins the forward and backward trees at the meeting node, returning the path from the end node to the start
+    /// node like `backtrack`, with its cost evaluated forwards from the departure time.
+    fn backtrack_bidirectional(&self, time_in_hour : Cost) -> (Box<[Index]>, Cost, Cost) {
+        let length = self.get_connection_len(self.meeting_node) as usize + self.backward_connection_lens[self.meeting_node as usize] as usize;
+        let mut path = Vec::with_capacity(length + 1);
+        let mut current = self.meeting_node;
+        path.push(current);
+        while current != self.start_node {
+            current = self.get_previous(current);
+            path.push(current);
+        }
+        path.reverse();
+        current = self.meeting_node;
+        while current != self.end_node {
+            current = self.next_indices[current as usize];
+            path.push(current);
+        }
+        let (distance, time) = self.evaluate_path(&path, time_in_hour);
+        path.reverse();
+        (path.into_boxed_slice(), distance, time)
+    }
+
+    /// Segments of a path given from its first to its last node, weighed forwards from a departure at `time_in_hour`.
+    pub fn path_segments(&self, path : &[Index], time_in_hour : Cost) -> Box<[PathSegment]> {
+        let mut segments = Vec::with_capacity(path.len().saturating_sub(1));
+        let mut time = 0.0;
+        for pair in path.windows(2) {
+            if let Some(connection) = self.find_connection(pair[0], pair[1]) {
+                let node = self.nodes[pair[0] as usize].get();
+                let weight = self.calculate_weight(connection, node, time_in_hour + time);
+                let base_time = self.cost_model.free_flow(connection);
+                time += weight;
+                segments.push(PathSegment {
+                    from : pair[0],
+                    to : pair[1],
+                    distance : connection.cost,
+                    base_time,
+                    penalty : weight - base_time,
+                    arrival : time
+                });
+            }
+        }
+        segments.into_boxed_slice()
+    }
+
+    /// Distance and cost of a path given from its first to its last node, departing at `time_in_hour`.
+    fn evaluate_path(&self, path : &[Index], time_in_hour : Cost) -> (Cost, Cost) {
+        let segments = self.path_segments(path, time_in_hour);
+        let distance = segments.iter().map(|segment| segment.distance).sum();
+        (distance, segments.last().map_or(0.0, |segment| segment.arrival))
+    }
+
+    /// Cheapest path from the start to the end node, from its first to its last node, with the weight of every
+    /// connection in `penalties` raised by that fraction.
+    fn penalized_path(&mut self, time_in_hour : Cost, penalties : &HashMap<(Index, Index), Cost>) -> Option<Vec<Index>> {
+        let nodes = self.nodes;
+        let end_node_index = self.end_node;
+        self.reset();
+        while let Some((key, index)) = self.heap.pop() {
+            let local_cost = self.costs[index as usize];
+            if key < Self::to_key(local_cost) {
+                continue;
+            }
+            if index == end_node_index {
+                break;
+            }
+            self.settled_nodes += 1;
+            let node = nodes[index as usize].get();
+            for connection in node.get_connections() {
+                let penalty = penalties.get(&(index, connection.index)).copied().unwrap_or(0.0);
+                let weight = self.calculate_weight(connection, node, time_in_hour + local_cost);
+                let new_cost = local_cost + weight * (1.0 + penalty);
+                if new_cost < self.costs[connection.index as usize] {
+                    self.costs[connection.index as usize] = new_cost;
+                    self.previous_indices[connection.index as usize] = index;
+                    self.heap.push(Self::to_key(new_cost), connection.index);
+                }
+            }
+        }
+        self.heap.clear();
+        if !self.has_visited(end_node_index) {
+            return None;
+        }
+        let mut path = vec![end_node_index];
+        let mut current = end_node_index;
+        while current != self.start_node {
+            current = self.get_previous(current);
+            path.push(current);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Finds up to `count` routes from the start to the end node with the penalty method: after every search the
+    /// connections of the route found get more expensive, pushing the next search onto other roads. A route is only
+    /// kept when at most `max_overlap` of its distance is shared with the routes kept before it. Routes are returned
+    /// best first, in the same `(path, time, distance)` form as `get_path_as_indices`.
+    pub fn find_alternatives(&mut self, count : usize, max_overlap : Cost) -> Vec<(Box<[Index]>, Cost, Cost)> {
+        let time_in_hour = self.departure_time;
+        let mut penalties : HashMap<(Index, Index), Cost> = HashMap::new();
+        let mut shared_connections : HashSet<(Index, Index)> = HashSet::new();
+        let mut routes = Vec::with_capacity(count);
+        for _ in 0..count * ALTERNATIVE_ATTEMPTS {
+            if routes.len() == count {
+                break;
+            }
+            let Some(mut path) = self.penalized_path(time_in_hour, &penalties) else {
+                break;
+            };
+            let (distance, time) = self.evaluate_path(&path, time_in_hour);
+            let shared_distance : Cost = path.windows(2)
+                .filter(|pair| shared_connections.contains(&(pair[0], pair[1])))
+                .filter_map(|pair| self.find_connection(pair[0], pair[1]))
+                .map(|connection| connection.cost)
+                .sum();
+            for pair in path.windows(2) {
+                *penalties.entry((pair[0], pair[1])).or_insert(0.0) += ALTERNATIVE_PENALTY;
+            }
+            if routes.is_empty() || (shared_distance <= distance * max_overlap && shared_distance < distance) {
+                shared_connections.extend(path.windows(2).map(|pair| (pair[0], pair[1])));
+                path.reverse();
+                routes.push((path.into_boxed_slice(), time, distance));
+            }
+        }
+        routes
+    }
+
+    /// Cost from `start` to `end` when departing at `departure`, found with the current search algorithm, or `None`
+    /// when the end node cannot be reached.
+    pub fn travel_time(&mut self, start : Index, end : Index, departure : DateTime<Utc>) -> Option<Cost> {
+        self.update_search(start, end, departure);
+        while !self.fully_searched() {
+            self.compute();
+        }
+        self.path.as_ref().map(|(_, time, _)| *time)
+    }
+
+    /// The departure from `earliest` to `latest`, to the minute, with the lowest cost from the start to the end node,
+    /// together with that cost, or `None` when the end node cannot be reached. Taken from the minimum of the travel
+    /// time `Profile` over the window, so it is exact for the time-dependent searches, `DIJKSTRA`, `ASTAR`, `LANDMARKS`
+    /// and `EDGES`. Waiting out outages is part of every search, so a later departure may well arrive no later.
+    pub fn optimal_departure(&mut self, earliest : DateTime<Utc>, latest : DateTime<Utc>) -> Option<(DateTime<Utc>, Cost)> {
+        let (start, end) = (self.start_node, self.end_node);
+        Profile::new(self, start, end, earliest, latest).fastest()
+            .map(|(minute, time)| (DateTime::from_timestamp(minute as i64 * 60, 0).expect("Departure out of range"), time))
+    }
+
     pub fn get_start_node_index(&self) -> usize {
         self.start_node as usize
     }
@@ -224,73 +865,170 @@ impl <'solver> Solver<'solver>  {
         })
     }
 
+    /// The segments of the path found, from the start node to the end node. Paths from the time-dependent searches,
+    /// the bidirectional one included, carry the weights the search itself used, while paths from the hierarchies and
+    /// the edge search are weighed forwards from the departure afterwards, leaving out turn costs.
+    pub fn get_path_segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Stores a path given from the end node to the start node, weighing its segments forwards when the search did
+    /// not record them.
+    fn store_path(&mut self, (path, distance, time) : (Box<[Index]>, Cost, Cost), segments : Option<Box<[PathSegment]>>) {
+        self.segments = segments.unwrap_or_else(|| {
+            let mut forwards = path.to_vec();
+            forwards.reverse();
+            self.path_segments(&forwards, self.departure_time)
+        });
+        self.path = Some((path, time, distance));
+    }
+
+    /// Stores a path found over the static weights of a hierarchy with the cost of driving it from the departure, so
+    /// that the cost reported matches its segments.
+    fn store_reweighed_path(&mut self, path : Box<[Index]>, distance : Cost) {
+        let mut forwards = path.to_vec();
+        forwards.reverse();
+        let segments = self.path_segments(&forwards, self.departure_time);
+        let time = segments.last().map_or(0.0, |segment| segment.arrival);
+        self.store_path((path, distance, time), Some(segments));
+    }
+
+    fn compute_contraction(&mut self) {
+        let hierarchy = self.contraction_hierarchy.expect("Contraction search requires a hierarchy, see Solver::set_contraction_hierarchy");
+        let query = self.hierarchy_query.get_or_insert_with(|| HierarchyQuery::new(self.nodes.len()));
+        let result = query.find_path(hierarchy, self.start_node, self.end_node);
+        self.settled_nodes = query.settled_nodes;
+        self.heap.clear();
+        match result {
+            Some((path, distance, _)) => self.store_reweighed_path(path, distance),
+            None => println!("No path found")
+        }
+    }
+
+    fn compute_customizable(&mut self) {
+        let hierarchy = self.customizable_hierarchy.expect("Customizable search requires a hierarchy, see Solver::set_customizable_hierarchy");
+        hierarchy.get_mut().customize_for(self.nodes, &self.outage_clock());
+        let query = self.customizable_query.get_or_insert_with(|| CustomizableQuery::new(self.nodes.len()));
+        let result = query.find_path(hierarchy.get(), self.start_node, self.end_node);
+        self.settled_nodes = query.settled_nodes;
+        self.heap.clear();
+        match result {
+            Some((path, distance, _)) => self.store_reweighed_path(path, distance),
+            None => println!("No path found")
+        }
+    }
+
+    fn compute_edge_based(&mut self) {
+        let turn_graph = self.turn_graph.expect("Edge based search requires a turn graph, see Solver::set_turn_graph");
+        let outage_clock = self.outage_clock();
+        let search = self.edge_search.get_or_insert_with(|| EdgeSearch::new(turn_graph.len()));
+        let result = search.find_path(turn_graph, self.nodes, &self.cost_model, &outage_clock, self.start_node, self.end_node);
+        self.settled_nodes = search.settled_nodes;
+        self.heap.clear();
+        match result {
+            Some(path) => self.store_path(path, None),
+            None => println!("No path found")
+        }
+    }
+
     pub fn compute(&mut self) {
-        if self.path.is_none() {
+        if self.path.is_none() && self.search_algorithm == SearchAlgorithm::BIDIRECTIONAL {
+            self.compute_bidirectional();
+            self.current_iteration = 0;
+            if self.fully_searched() {
+                if self.has_visited(self.end_node) {
+                    let path = self.backtrack();
+                    let segments = self.recorded_segments(&path.0);
+                    self.store_path(path, Some(segments));
+                } else if self.meeting_cost != Cost::MAX {
+                    let time_in_hour = self.departure_time;
+                    let path = self.backtrack_bidirectional(time_in_hour);
+                    self.store_path(path, None);
+                } else {
+                    println!("No path found");
+                }
+            }
+        } else if self.path.is_none() && self.uses_contraction_hierarchy() {
+            self.compute_contraction();
+        } else if self.path.is_none() && self.uses_customizable_hierarchy() {
+            self.compute_customizable();
+        } else if self.path.is_none() && self.search_algorithm == SearchAlgorithm::EDGES && self.turn_graph.is_some() {
+            self.compute_edge_based();
+        } else if self.path.is_none() {
+            if self.search_algorithm == SearchAlgorithm::ASTAR && self.network_bounds.is_none() {
+                self.network_bounds = Some(NetworkBounds::new(self.nodes));
+            }
             self.compute_radix();
             self.merge();
             self.current_iteration = 0;
             let end_index = self.end_node;
             if self.has_visited(end_index) {
-                let (path, distance, time) = self.backtrack();
-                let path_len = path.len();
-                self.path = Some((path, time, distance));
-                println!("Found path of length {path_len}");
+                let path = self.backtrack();
+                let segments = self.recorded_segments(&path.0);
+                self.store_path(path, Some(segments));
             } else {
                 println!("No path found");
             }
         }
     }
 
-    fn get_distance(&self, index: Index) -> Cost {
-        self.previous_distances[index as usize]
-    }
     
+    /// The path found to the end node, from the end node to the start node, with its distance and the cost the search
+    /// reached the end node at.
     pub fn backtrack(&self) -> (Box<[Index]>, Cost, Cost) {
         let length = self.get_connection_len(self.end_node) as usize;
-        let mut path = Vec::with_capacity(length);
+        let mut path = Vec::with_capacity(length + 1);
         let mut previous_node = self.end_node;
         let mut distance = 0.0;
-        let mut time = 0.0;
         let node_length = self.nodes.len() as u32;
+        path.push(previous_node);
         for _ in 0..length {
-            path.push(previous_node);
             let previous_index = self.get_previous(previous_node);
-            if previous_index < node_length {
-                let mut connection_cost = self.get_distance(previous_index);
-                let time_offset = self.get_cost(previous_index);
-                let node = self.nodes[previous_index as usize].get();
-                distance += connection_cost;
-                connection_cost /= (node.connections[0].speed as Cost);
-                time += match node.node_type {
-                    NodeType::Normal => connection_cost,
-                    NodeType::NearTrafficLight => connection_cost + connection_cost * (3 as Cost) * Self::is_load_shedding(node.flag, time_offset),
-                    NodeType::AtTrafficLight => connection_cost + connection_cost * (5 as Cost) * Self::is_load_shedding(node.flag, time_offset)
-                };
-                if previous_index != u32::MAX {
-                    previous_node = previous_index as Index;
-                } else {
-                    break;
-                }
-            } else {
+            if previous_index >= node_length {
                 break;
             }
+            distance += self.previous_distances[previous_node as usize];
+            previous_node = previous_index;
+            path.push(previous_node);
         }
-        path.push(previous_node);
-        (path.into_boxed_slice(), distance, time)
+        (path.into_boxed_slice(), distance, self.get_cost(self.end_node))
+    }
+
+    /// Segments of a path from `backtrack`, from the start node to the end node, with the weights and arrival times
+    /// recorded while searching.
+    fn recorded_segments(&self, path : &[Index]) -> Box<[PathSegment]> {
+        path.windows(2).rev().map(|pair| {
+            let to = pair[0] as usize;
+            PathSegment {
+                from : pair[1],
+                to : pair[0],
+                distance : self.previous_distances[to],
+                base_time : self.previous_base_times[to],
+                penalty : self.previous_weights[to] - self.previous_base_times[to],
+                arrival : self.costs[to]
+            }
+        }).collect()
     }
 
     pub fn reset(&mut self) {
         self.heap.clear();
         self.backup_heap.clear();
         self.path = None;
+        self.segments = Box::new([]);
         self.costs.as_slice_mut().par_iter_mut().for_each(|x| {*x = Cost::MAX});
         self.previous_indices.as_slice_mut().par_iter_mut().for_each(|x| {*x = u32::MAX});
         self.connection_lens.as_slice_mut().par_iter_mut().for_each(|x| {*x = 0u16});
         self.previous_distances.as_slice_mut().par_iter_mut().for_each(|x| {*x = 0.0});
         self.current_iteration = 0;
         self.total_iterations = 0;
+        self.settled_nodes = 0;
+        self.meeting_node = Index::MAX;
+        self.meeting_cost = Cost::MAX;
         self.heap.push(MAX_TIME, self.start_node);
         self.costs[self.start_node as usize] = 0f64 as Cost;
+        if self.search_algorithm == SearchAlgorithm::BIDIRECTIONAL || self.has_backward_state() {
+            self.reset_backward();
+        }
     }
 }
 
@@ -298,4 +1036,141 @@ unsafe impl <'solver> Sync for Solver<'solver>  {
 }
 
 unsafe impl <'solver> Send for Solver<'solver>{
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::load_shedding::OutageInterval;
+    use crate::objects::pathing::test_graph::{grid, same_cost, search, search_at};
+    use chrono::TimeDelta;
+
+    #[test]
+    fn search_without_path_finishes_after_bidirectional_search() {
+        let nodes = grid(5, 5, 1);
+        let reverse_graph = ReverseGraph::new(nodes.get_slice());
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        solver.set_reverse_graph(&reverse_graph);
+        solver.search_algorithm = SearchAlgorithm::BIDIRECTIONAL;
+        assert!(search(&mut solver, 0, 24).is_some());
+        for algorithm in [SearchAlgorithm::CONTRACTION, SearchAlgorithm::CUSTOMIZABLE, SearchAlgorithm::EDGES, SearchAlgorithm::BIDIRECTIONAL] {
+            solver.search_algorithm = algorithm;
+            assert_eq!(search(&mut solver, 0, 25), None);
+        }
+    }
+
+    #[test]
+    fn bidirectional_matches_dijkstra_under_timed_outages() {
+        // The lights are out at departure, but come back on before the search gets far.
+        let nodes = grid(10, 10, 0);
+        let departure = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (departure.timestamp() / 60) as u32;
+        for cell in nodes.get_slice().iter().filter(|cell| cell.get().index % 3 == 1) {
+            let node = cell.get_mut();
+            node.node_type = NodeType::AtTrafficLight;
+            node.outages = Box::new([OutageInterval { start : minute, end : minute + 1 }]);
+        }
+        let reverse_graph = ReverseGraph::new(nodes.get_slice());
+        for search_method in [SearchMethod::FASTEST, SearchMethod::AVOID] {
+            let mut dijkstra = Solver::new(nodes.get_slice(), 0, 0, 100_000, search_method);
+            let mut bidirectional = Solver::new(nodes.get_slice(), 0, 0, 100_000, search_method);
+            bidirectional.set_reverse_graph(&reverse_graph);
+            bidirectional.search_algorithm = SearchAlgorithm::BIDIRECTIONAL;
+            for start in [0, 9, 45, 90, 99] {
+                for end in 0..100 {
+                    let expected = search_at(&mut dijkstra, start, end, departure);
+                    let found = search_at(&mut bidirectional, start, end, departure);
+                    assert!(same_cost(found, expected), "{search_method:?} from {start} to {end}: {found:?} against {expected:?}");
+                    let segments = bidirectional.get_path_segments();
+                    assert!(same_cost(segments.last().map(|segment| segment.arrival).or(Some(0.0)), found));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn astar_matches_dijkstra_with_given_or_lazy_bounds() {
+        let nodes = grid(6, 6, 0);
+        let mut dijkstra = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        let mut given = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        let mut lazy = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        given.set_network_bounds(NetworkBounds::new(nodes.get_slice()));
+        given.search_algorithm = SearchAlgorithm::ASTAR;
+        lazy.search_algorithm = SearchAlgorithm::ASTAR;
+        assert!(lazy.network_bounds.is_none());
+        for (start, end) in [(0, 35), (35, 0), (5, 30), (14, 21), (7, 7)] {
+            let expected = search(&mut dijkstra, start, end);
+            assert!(same_cost(search(&mut given, start, end), expected), "{start} to {end}");
+            assert!(same_cost(search(&mut lazy, start, end), expected), "{start} to {end}");
+        }
+        assert_eq!(lazy.network_bounds, given.network_bounds);
+    }
+
+    #[test]
+    fn searches_longer_than_the_largest_key_finish() {
+        let nodes = grid(4, 1, 0);
+        for cell in nodes.get_slice() {
+            for connection in cell.get_mut().connections.iter_mut() {
+                connection.cost = 10.0;
+                connection.speed = 1;
+            }
+        }
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        assert!(same_cost(search(&mut solver, 0, 3), Some(30.0)));
+        assert!(same_cost(Some(solver.one_to_many(0, &[3])[0]), Some(30.0)));
+        assert_eq!(solver.free_flow_costs(0, false)[3], 30.0);
+    }
+
+    #[test]
+    fn bounded_search_cutoff_stops_at_the_largest_key() {
+        let nodes = grid(4, 1, 0);
+        for cell in nodes.get_slice() {
+            for connection in cell.get_mut().connections.iter_mut() {
+                connection.cost = 10.0;
+                connection.speed = 1;
+            }
+        }
+        let mut solver = Solver::new(nodes.get_slice(), 0, 0, 100_000, SearchMethod::FASTEST);
+        assert_eq!(*solver.bounded_search(0, u32::MAX), [0, 1]);
+    }
+
+    #[test]
+    fn outages_count_from_when_the_light_is_reached() {
+        // Every connection takes 10 minutes, so the light at 1 is reached 10 minutes after departure.
+        let nodes = grid(3, 1, 0);
+        for cell in nodes.get_slice() {
+            for connection in cell.get_mut().connections.iter_mut() {
+                connection.cost = 10.0;
+                connection.speed = 60;
+            }
+        }
+        nodes.get_slice()[1].get_mut().node_type = NodeType::AtTrafficLight;
+        let departure = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (departure.timestamp() / 60) as u32;
+        let mut solver = Solver::new(nodes.get_slice(), 0, 2, 100_000, SearchMethod::FASTEST);
+        let mut search_with_outage = |start : u32, end : u32| {
+            nodes.get_slice()[1].get_mut().outages = Box::new([OutageInterval { start : minute + start, end : minute + end }]);
+            search_at(&mut solver, 0, 2, departure).unwrap()
+        };
+        let free_flow = search_with_outage(1000, 1060);
+        assert!(search_with_outage(5, 15) > free_flow + 4.0 / 60.0);
+        assert!(same_cost(Some(search_with_outage(0, 5)), Some(free_flow)));
+        assert!(same_cost(Some(search_with_outage(15, 30)), Some(free_flow)));
+    }
+
+    #[test]
+    fn optimal_departure_waits_for_the_lights() {
+        let nodes = grid(3, 1, 0);
+        let outage_start = DateTime::from_timestamp(1_700_000_000 / 60 * 60, 0).unwrap();
+        let minute = (outage_start.timestamp() / 60) as u32;
+        let traffic_light = nodes.get_slice()[1].get_mut();
+        traffic_light.node_type = NodeType::AtTrafficLight;
+        traffic_light.outages = Box::new([OutageInterval { start : minute, end : minute + 120 }]);
+        let mut solver = Solver::new(nodes.get_slice(), 0, 2, 100_000, SearchMethod::FASTEST);
+        let free_flow = solver.travel_time(0, 2, outage_start + TimeDelta::minutes(200));
+        assert!(solver.travel_time(0, 2, outage_start + TimeDelta::minutes(60)) > free_flow);
+        let (departure, time) = solver.optimal_departure(outage_start + TimeDelta::minutes(10), outage_start + TimeDelta::minutes(180)).unwrap();
+        assert_eq!(departure, outage_start + TimeDelta::minutes(120));
+        assert!(same_cost(Some(time), free_flow));
+    }
+}
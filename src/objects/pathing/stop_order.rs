@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use crate::compute_matrix;
+use crate::objects::pathing::solver::Solver;
+use crate::types::{Cost, Index};
+
+/// Stand-in cost for a pair of stops without a path, large enough that no order uses one unless it has to, yet
+/// finite so that the cost differences the improvements work with stay meaningful.
+const UNREACHABLE : f64 = 1.0e6;
+/// Hours a move has to save to count as an improvement, so that rounding cannot make moves undo each other forever.
+const IMPROVEMENT : f64 = 1.0e-9;
+/// Longest run of consecutive stops an Or-opt move relocates.
+const OR_OPT_LENGTH : usize = 3;
+
+/// An order to visit a set of stops in, found on their travel time matrix. Travel times need not be symmetric.
+pub struct StopOrder {
+    /// Positions in the given stops, in visiting order.
+    pub order : Box<[usize]>
+}
+
+impl StopOrder {
+    /// Builds the travel time matrix between `stops` with the search method, cost model and utc offset of `solver`,
+    /// every trip departing at `departure_time`, and orders them. The stops at positions `start` and `end`, when given,
+    /// are visited first and last. They may be the same stop for a round trip.
+    pub fn new(stops : &[Index], start : Option<usize>, end : Option<usize>, solver : &Solver, departure_time : DateTime<Utc>) -> Self {
+        let matrix = compute_matrix(solver.get_nodes(), stops, stops, solver.search_method, solver.cost_model, departure_time, solver.utc_offset);
+        Self::from_matrix(&matrix, stops.len(), start, end)
+    }
+
+    /// Orders `size` stops given the travel time between every pair, flattened row by row like `compute_matrix`.
+    /// Builds a nearest-neighbour tour from every possible first stop, keeps the cheapest, and improves it with 2-opt
+    /// and Or-opt moves until neither finds anything better.
+    pub fn from_matrix(matrix : &[Cost], size : usize, start : Option<usize>, end : Option<usize>) -> Self {
+        let cost = |from : usize, to : usize| -> f64 {
+            match matrix[from * size + to] {
+                Cost::MAX => UNREACHABLE,
+                cost => cost as f64
+            }
+        };
+        let free : Vec<usize> = (0..size).filter(|stop| Some(*stop) != start && Some(*stop) != end).collect();
+        let firsts : Vec<Option<usize>> = if start.is_some() || free.is_empty() {
+            vec![start]
+        } else {
+            free.iter().map(|stop| Some(*stop)).collect()
+        };
+        let mut tour = firsts.into_iter()
+            .map(|first| nearest_neighbour(first, &free, end, &cost))
+            .min_by(|a, b| tour_cost(a, &cost).total_cmp(&tour_cost(b, &cost)))
+            .unwrap_or_default();
+        let lowest = start.map_or(0, |_| 1);
+        let highest = tour.len() - end.map_or(0, |_| 1);
+        while improve_two_opt(&mut tour, lowest, highest, &cost) || improve_or_opt(&mut tour, lowest, highest, &cost) {}
+        Self {
+            order : tour.into_boxed_slice()
+        }
+    }
+}
+
+fn tour_cost(tour : &[usize], cost : &impl Fn(usize, usize) -> f64) -> f64 {
+    tour.windows(2).map(|pair| cost(pair[0], pair[1])).sum()
+}
+
+/// Cost between two positions of a tour, 0 when either lies outside it.
+fn edge(from : Option<usize>, to : Option<usize>, cost : &impl Fn(usize, usize) -> f64) -> f64 {
+    match (from, to) {
+        (Some(from), Some(to)) => cost(from, to),
+        _ => 0.0
+    }
+}
+
+/// Starts at `first` and keeps going to the closest stop not visited yet, finishing at `end`.
+fn nearest_neighbour(first : Option<usize>, free : &[usize], end : Option<usize>, cost : &impl Fn(usize, usize) -> f64) -> Vec<usize> {
+    let mut tour : Vec<usize> = first.into_iter().collect();
+    let mut remaining : Vec<usize> = free.iter().copied().filter(|stop| Some(*stop) != first).collect();
+    while !remaining.is_empty() {
+        let next = match tour.last() {
+            Some(last) => (0..remaining.len())
+                .min_by(|a, b| cost(*last, remaining[*a]).total_cmp(&cost(*last, remaining[*b])))
+                .unwrap_or(0),
+            None => 0
+        };
+        tour.push(remaining.swap_remove(next));
+    }
+    tour.extend(end);
+    tour
+}
+
+/// Reverses the first run of stops within `lowest..highest` whose reversal makes the tour cheaper. Reversing a run
+/// also reverses every travel time inside it, which prefix sums in both directions give in constant time.
+fn improve_two_opt(tour : &mut [usize], lowest : usize, highest : usize, cost : &impl Fn(usize, usize) -> f64) -> bool {
+    let mut forward = vec![0.0; tour.len()];
+    let mut backward = vec![0.0; tour.len()];
+    for position in 1..tour.len() {
+        forward[position] = forward[position - 1] + cost(tour[position - 1], tour[position]);
+        backward[position] = backward[position - 1] + cost(tour[position], tour[position - 1]);
+    }
+    for first in lowest..highest {
+        let previous = first.checked_sub(1).map(|position| tour[position]);
+        for last in first + 1..highest {
+            let next = tour.get(last + 1).copied();
+            let before = edge(previous, Some(tour[first]), cost) + forward[last] - forward[first] + edge(Some(tour[last]), next, cost);
+            let after = edge(previous, Some(tour[last]), cost) + backward[last] - backward[first] + edge(Some(tour[first]), next, cost);
+            if after < before - IMPROVEMENT {
+                tour[first..=last].reverse();
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Moves the first run of up to `OR_OPT_LENGTH` stops within `lowest..highest` whose relocation elsewhere within that
+/// range makes the tour cheaper.
+fn improve_or_opt(tour : &mut Vec<usize>, lowest : usize, highest : usize, cost : &impl Fn(usize, usize) -> f64) -> bool {
+    let at = |tour : &[usize], position : Option<usize>| position.and_then(|position| tour.get(position).copied());
+    for length in 1..=OR_OPT_LENGTH {
+        for first in lowest..(highest + 1).saturating_sub(length) {
+            let last = first + length - 1;
+            let previous = at(tour, first.checked_sub(1));
+            let next = at(tour, Some(last + 1));
+            let removed = edge(previous, Some(tour[first]), cost) + edge(Some(tour[last]), next, cost) - edge(previous, next, cost);
+            for insert in lowest..=highest {
+                if insert >= first && insert <= last + 1 {
+                    continue;
+                }
+                let before = at(tour, insert.checked_sub(1));
+                let after = at(tour, Some(insert));
+                let added = edge(before, Some(tour[first]), cost) + edge(Some(tour[last]), after, cost) - edge(before, after, cost);
+                if added < removed - IMPROVEMENT {
+                    let run : Vec<usize> = tour.drain(first..=last).collect();
+                    let insert = if insert > last { insert - length } else { insert };
+                    tour.splice(insert..insert, run);
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Travel times between points given by their coordinates, in the same units.
+    fn matrix(points : &[(f64, f64)]) -> Vec<Cost> {
+        points.iter().flat_map(|from| points.iter().map(|to| (from.0 - to.0).hypot(from.1 - to.1) as Cost)).collect()
+    }
+
+    #[test]
+    fn visits_stops_along_a_line_in_order() {
+        let points = [(3.0, 0.0), (0.0, 0.0), (4.0, 0.0), (1.0, 0.0), (2.0, 0.0)];
+        let order = StopOrder::from_matrix(&matrix(&points), points.len(), Some(1), None).order;
+        assert_eq!(*order, [1, 3, 4, 0, 2]);
+        let order = StopOrder::from_matrix(&matrix(&points), points.len(), None, Some(1)).order;
+        assert_eq!(*order, [2, 0, 4, 3, 1]);
+    }
+
+    #[test]
+    fn round_trip_goes_around_the_circle() {
+        let angles = [0, 5, 2, 7, 1, 4, 6, 3];
+        let points : Vec<(f64, f64)> = angles.iter().map(|step| {
+            let angle = *step as f64 * std::f64::consts::TAU / angles.len() as f64;
+            (angle.cos(), angle.sin())
+        }).collect();
+        let order = StopOrder::from_matrix(&matrix(&points), points.len(), Some(0), Some(0)).order;
+        let steps : Vec<usize> = order.iter().map(|position| angles[*position]).collect();
+        assert!(steps == [0, 1, 2, 3, 4, 5, 6, 7, 0] || steps == [0, 7, 6, 5, 4, 3, 2, 1, 0], "{steps:?}");
+    }
+
+    #[test]
+    fn avoids_pairs_without_a_path() {
+        let mut costs = matrix(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)]);
+        costs[4 + 2] = Cost::MAX;
+        let order = StopOrder::from_matrix(&costs, 4, Some(0), Some(3)).order;
+        assert_eq!(*order, [0, 2, 1, 3]);
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::simd::Simd;
+use crate::distance;
+use crate::objects::pathing::connection::{Connection, RoadClass};
+use crate::objects::pathing::node::Node;
+use crate::objects::pathing::solver::Solver;
+use crate::objects::util::parallel_list::ParallelList;
+use crate::types::{Cost, Index, Pos};
+
+/// Spacing of the grid in degrees, roughly 100 metres.
+const SPACING : Pos = 0.001;
+
+/// A `width` by `height` grid of two-way roads, every connection costing its length in kilometres stretched by a
+/// fixed pseudo-random factor of up to 1.5, followed by `isolated` nodes without any connections.
+pub fn grid(width : usize, height : usize, isolated : usize) -> ParallelList<Node> {
+    let size = width * height;
+    let nodes = ParallelList::new(size + isolated);
+    let position = |index : usize| Simd::from_array([28.0 + (index % width) as Pos * SPACING, -26.0 + (index / width) as Pos * SPACING]);
+    for index in 0..size {
+        let (x, y) = (index % width, index / width);
+        let neighbours = [
+            (x + 1 < width).then(|| index + 1),
+            (x > 0).then(|| index - 1),
+            (y + 1 < height).then(|| index + width),
+            (y > 0).then(|| index - width)
+        ];
+        let connections : Vec<Connection> = neighbours.into_iter().flatten().map(|neighbour| {
+            let stretch = 1.0 + ((index * 7 + neighbour * 13) % 50) as Cost / 100.0;
+            Connection {
+                index : neighbour as Index,
+                cost : distance(&position(index), &position(neighbour)) as Cost / 1000.0 * stretch,
+                speed : 40 + ((index + neighbour) % 5) as u16 * 20,
+                road_class : RoadClass::Unclassified,
+                one_way : false
+            }
+        }).collect();
+        nodes.insert(Node::new(index as Index, position(index), connections.into_boxed_slice()), index);
+    }
+    for index in size..size + isolated {
+        nodes.insert(Node::new(index as Index, position(index), Box::new([])), index);
+    }
+    nodes
+}
+
+/// Runs a search from `start` to `end` departing now to the end, returning the cost of the path found.
+pub fn search(solver : &mut Solver, start : Index, end : Index) -> Option<Cost> {
+    search_at(solver, start, end, Utc::now())
+}
+
+/// Runs a search from `start` to `end` departing at `departure` to the end, returning the cost of the path found.
+/// Fails instead of hanging when the search never finishes.
+pub fn search_at(solver : &mut Solver, start : Index, end : Index, departure : DateTime<Utc>) -> Option<Cost> {
+    solver.update_search(start, end, departure);
+    for _ in 0..1000 {
+        if solver.fully_searched() {
+            return solver.get_path_as_indices().as_ref().map(|(_, time, _)| *time);
+        }
+        solver.compute();
+    }
+    panic!("Search from {start} to {end} never finished");
+}
+
+/// Whether two costs are equal up to the rounding of summing them in another order.
+pub fn same_cost(first : Option<Cost>, second : Option<Cost>) -> bool {
+    match (first, second) {
+        (Some(first), Some(second)) => (first - second).abs() <= 1e-5 * first.max(1.0),
+        (first, second) => first.is_none() && second.is_none()
+    }
+}
+
+
+/// Searches from a few start nodes to every node with `solver` and with plain Dijkstra under the same cost model,
+/// asserting that both find the same cost and that the segments of every path found add up to it. `check` is handed
+/// the start, the end and the cost Dijkstra found for whatever else a test asserts about each pair.
+pub fn assert_matches_dijkstra(solver : &mut Solver, mut check : impl FnMut(Index, Index, Option<Cost>)) {
+    let nodes = solver.get_nodes();
+    let mut dijkstra = Solver::new(nodes, 0, 0, 100_000, solver.search_method);
+    dijkstra.cost_model = solver.cost_model;
+    for start in [0, 7, 20, 35].into_iter().filter(|start| (*start as usize) < nodes.len()) {
+        for end in (0..nodes.len() as Index).filter(|end| *end != start) {
+            let expected = search(&mut dijkstra, start, end);
+            let cost = search(solver, start, end);
+            assert!(same_cost(expected, cost), "{start} to {end}: {expected:?} != {cost:?}");
+            let segments = solver.get_path_segments();
+            assert!(same_cost(cost, segments.last().map(|segment| segment.arrival)), "{start} to {end}");
+            check(start, end, expected);
+        }
+    }
+}
+
+/// Saves to a temporary file with `save`, and returns what `load` makes of it once the file is removed again.
+pub fn with_saved_file<T>(extension : &str, save : impl FnOnce(&Path) -> Result<(), String>, load : impl FnOnce(&Path) -> T) -> T {
+    let file_location = std::env::temp_dir().join(format!("round_trip_{}.{extension}", std::process::id()));
+    save(&file_location).expect("Failed to save");
+    let loaded = load(&file_location);
+    std::fs::remove_file(&file_location).expect("Failed to remove saved file");
+    loaded
+}
+
+/// Asserts that `from_bytes` reads `bytes` back, but fails on every truncation of them and without the header.
+pub fn assert_rejects_truncated<T>(bytes : &[u8], from_bytes : impl Fn(&[u8]) -> Result<T, String>) {
+    assert!(from_bytes(bytes).is_ok());
+    for length in [0, 8, 20, bytes.len() / 2, bytes.len() - 1] {
+        assert!(from_bytes(&bytes[..length]).is_err(), "Loaded {length} of {} bytes", bytes.len());
+    }
+    assert!(from_bytes(&bytes[12..]).is_err());
+}
@@ -0,0 +1,36 @@
+use crate::loader::read_i32;
+use crate::traits::{ByteConvertable, Indexable};
+use crate::types::Index;
+
+/// A banned turn: arriving at `via` from `from`, the vehicle may not continue to `to`. Edges are identified by the
+/// nodes at both ends, so the from-edge is `from -> via` and the to-edge is `via -> to`.
+#[derive(Clone, Copy)]
+pub struct TurnRestriction {
+    pub id : Index,
+    pub from : Index,
+    pub via : Index,
+    pub to : Index
+}
+
+impl Indexable for TurnRestriction {
+    #[inline]
+    fn index(&self) -> Index {
+        self.id
+    }
+}
+
+impl ByteConvertable for TurnRestriction {
+    fn from_bytes(byte_array: &[u8]) -> Self {
+        let mut index = 0;
+        let id = read_i32(byte_array, &mut index);
+        let from = read_i32(byte_array, &mut index);
+        let via = read_i32(byte_array, &mut index);
+        let to = read_i32(byte_array, &mut index);
+        Self {
+            id : id as Index,
+            from : from as Index,
+            via : via as Index,
+            to : to as Index
+        }
+    }
+}
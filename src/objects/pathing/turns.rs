@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+
+use radix_heap::RadixHeapMap;
+
+use crate::new_slice;
+use crate::objects::load_shedding::OutageClock;
+use crate::objects::pathing::connection::Connection;
+use crate::objects::pathing::cost_model::{CostModel, SpeedSource};
+use crate::objects::pathing::node::Node;
+use crate::objects::pathing::node_type::NodeType;
+use crate::objects::pathing::solver::Solver;
+use crate::objects::pathing::turn_restriction::TurnRestriction;
+use crate::objects::util::super_cell::SuperCell;
+use crate::types::{Cost, Index, Pos};
+use crate::MULTIPLIER;
+
+const NO_EDGE : u32 = u32::MAX;
+/// Turns sharper than this, in degrees, count as a left or right turn rather than carrying on straight.
+const TURN_ANGLE : Pos = 45.0;
+const SECONDS_TO_HOURS : Cost = 1.0 / 3600.0;
+
+/// Extra time in hours for turning at an intersection. Turns at `AtTrafficLight` nodes are charged separately, as
+/// waiting for a gap in oncoming traffic is much slower there, particularly when the lights are out.
+#[derive(Clone, Copy)]
+pub struct TurnCosts {
+    pub left : Cost,
+    pub right : Cost,
+    pub u_turn : Cost,
+    pub traffic_light_left : Cost,
+    pub traffic_light_right : Cost
+}
+
+impl Default for TurnCosts {
+    /// Traffic drives on the left, so right turns cross the oncoming lanes and cost the most.
+    fn default() -> Self {
+        Self {
+            left : 2.0 * SECONDS_TO_HOURS,
+            right : 6.0 * SECONDS_TO_HOURS,
+            u_turn : 30.0 * SECONDS_TO_HOURS,
+            traffic_light_left : 10.0 * SECONDS_TO_HOURS,
+            traffic_light_right : 30.0 * SECONDS_TO_HOURS
+        }
+    }
+}
+
+/// Numbers every connection so that the solver can search over edges instead of nodes, which is what makes turn
+/// restrictions and turn costs expressible. Edge `offsets[node] + i` is the `i`th connection of `node`.
+pub struct TurnGraph {
+    offsets : Box<[u32]>,
+    tails : Box<[Index]>,
+    restrictions : HashSet<(Index, Index, Index)>,
+    pub turn_costs : Option<TurnCosts>
+}
+
+impl TurnGraph {
+    pub fn new(nodes : &[SuperCell<Node>], restrictions : &[TurnRestriction]) -> Self {
+        let mut offsets = Vec::with_capacity(nodes.len() + 1);
+        let mut tails = Vec::new();
+        offsets.push(0u32);
+        for cell in nodes {
+            let node = cell.get();
+            tails.extend(node.get_connections().iter().map(|_| node.index));
+            offsets.push(tails.len() as u32);
+        }
+        Self {
+            offsets : offsets.into_boxed_slice(),
+            tails : tails.into_boxed_slice(),
+            restrictions : restrictions.iter().map(|restriction| (restriction.from, restriction.via, restriction.to)).collect(),
+            turn_costs : None
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.tails.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.tails.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn is_restricted(&self, from : Index, via : Index, to : Index) -> bool {
+        self.restrictions.contains(&(from, via, to))
+    }
+
+    #[inline(always)]
+    fn connection<'a>(&self, nodes : &'a [SuperCell<Node>], edge : u32) -> &'a Connection {
+        let tail = self.tails[edge as usize];
+        &nodes[tail as usize].get().get_connections()[(edge - self.offsets[tail as usize]) as usize]
+    }
+
+    /// Cost of turning from `from -> via` onto `via -> to`, from the angle between the two edges.
+    fn turn_cost(&self, turn_costs : &TurnCosts, nodes : &[SuperCell<Node>], from : Index, via : &Node, to : Index) -> Cost {
+        if from == to {
+            return turn_costs.u_turn;
+        }
+        let incoming = (via.position - nodes[from as usize].get().position) * MULTIPLIER;
+        let outgoing = (nodes[to as usize].get().position - via.position) * MULTIPLIER;
+        let cross = incoming[0] * outgoing[1] - incoming[1] * outgoing[0];
+        let dot = incoming[0] * outgoing[0] + incoming[1] * outgoing[1];
+        let angle = cross.atan2(dot).to_degrees();
+        let at_traffic_light = via.node_type == NodeType::AtTrafficLight;
+        match (angle > TURN_ANGLE, angle < -TURN_ANGLE, at_traffic_light) {
+            (true, _, false) => turn_costs.left,
+            (true, _, true) => turn_costs.traffic_light_left,
+            (_, true, false) => turn_costs.right,
+            (_, true, true) => turn_costs.traffic_light_right,
+            _ => 0.0
+        }
+    }
+}
+
+/// Reusable state for searches over the edges of a `TurnGraph`, where the cost of an edge is the cost of arriving at
+/// its head node through it.
+pub struct EdgeSearch {
+    costs : Box<[Cost]>,
+    previous : Box<[u32]>,
+    heap : RadixHeapMap<u32, u32>,
+    touched : Vec<u32>,
+    pub settled_nodes : u32
+}
+
+impl EdgeSearch {
+    pub fn new(size : usize) -> Self {
+        Self {
+            costs : new_slice(Cost::MAX, size),
+            previous : new_slice(NO_EDGE, size),
+            heap : RadixHeapMap::new(),
+            touched : Vec::new(),
+            settled_nodes : 0
+        }
+    }
+
+    fn reset(&mut self) {
+        for edge in self.touched.drain(..) {
+            self.costs[edge as usize] = Cost::MAX;
+            self.previous[edge as usize] = NO_EDGE;
+        }
+        self.heap.clear();
+        self.settled_nodes = 0;
+    }
+
+    #[inline]
+    fn visit(&mut self, edge : u32, cost : Cost, previous : u32) {
+        if cost < self.costs[edge as usize] {
+            if self.costs[edge as usize] == Cost::MAX {
+                self.touched.push(edge);
+            }
+            self.costs[edge as usize] = cost;
+            self.previous[edge as usize] = previous;
+            self.heap.push(Solver::to_key(cost), edge);
+        }
+    }
+
+    /// Finds the cheapest path that obeys the turn restrictions, never turns back on itself except at a dead end, and
+    /// pays the turn costs if any are set, unless `cost_model` drives at a fixed speed and so weighs distance rather
+    /// than time. The path is returned from the end node to the start node like `Solver::backtrack`, together with its
+    /// distance and cost.
+    pub fn find_path(&mut self, graph : &TurnGraph, nodes : &[SuperCell<Node>], cost_model : &CostModel, outage_clock : &OutageClock, start : Index, end : Index) -> Option<(Box<[Index]>, Cost, Cost)> {
+        self.reset();
+        if start == end {
+            return Some((Box::new([start]), 0.0, 0.0));
+        }
+        let turn_costs = match cost_model.speed {
+            SpeedSource::Fixed(_) => None,
+            SpeedSource::Connection => graph.turn_costs
+        };
+        let time_in_hour = outage_clock.departure;
+        let start_node = nodes[start as usize].get();
+        for (position, connection) in start_node.get_connections().iter().enumerate() {
+            let cost = cost_model.weight(connection, start_node.node_type, outage_clock.wait(start_node, time_in_hour));
+            self.visit(graph.offsets[start as usize] + position as u32, cost, NO_EDGE);
+        }
+        let mut last_edge = NO_EDGE;
+        while let Some((key, edge)) = self.heap.pop() {
+            if last_edge != NO_EDGE && key < Solver::to_key(self.costs[last_edge as usize]) {
+                // Keys are whole seconds, so cheaper edges into the end node can still follow within the same key.
+                break;
+            }
+            let local_cost = self.costs[edge as usize];
+            if key < Solver::to_key(local_cost) {
+                continue;
+            }
+            self.settled_nodes += 1;
+            let from = graph.tails[edge as usize];
+            let via = graph.connection(nodes, edge).index;
+            if via == end {
+                if last_edge == NO_EDGE || local_cost < self.costs[last_edge as usize] {
+                    last_edge = edge;
+                }
+                continue;
+            }
+            let via_node = nodes[via as usize].get();
+            let wait = outage_clock.wait(via_node, time_in_hour + local_cost);
+            let connections = via_node.get_connections();
+            for (position, connection) in connections.iter().enumerate() {
+                let to = connection.index;
+                if (to == from && connections.len() > 1) || graph.is_restricted(from, via, to) {
+                    continue;
+                }
+                let mut cost = local_cost + cost_model.weight(connection, via_node.node_type, wait);
+                if let Some(turn_costs) = &turn_costs {
+                    cost += graph.turn_cost(turn_costs, nodes, from, via_node, to);
+                }
+                self.visit(graph.offsets[via as usize] + position as u32, cost, edge);
+            }
+        }
+        self.heap.clear();
+        if last_edge == NO_EDGE {
+            return None;
+        }
+        let mut path = vec![end];
+        let mut distance = 0.0;
+        let mut edge = last_edge;
+        while edge != NO_EDGE {
+            distance += graph.connection(nodes, edge).cost;
+            path.push(graph.tails[edge as usize]);
+            edge = self.previous[edge as usize];
+        }
+        Some((path.into_boxed_slice(), distance, self.costs[last_edge as usize]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::objects::pathing::node_type::SearchMethod;
+    use crate::objects::pathing::solver::SOUTH_AFRICA_UTC_OFFSET;
+    use crate::objects::pathing::test_graph::grid;
+    use crate::objects::util::parallel_list::ParallelList;
+
+    /// A T junction at node 1 of the road 0 - 1 - 2 - 3, with a dead end 5 branching off it.
+    fn junction() -> ParallelList<Node> {
+        let nodes = grid(4, 2, 0);
+        let removed = [4, 6, 7];
+        for cell in nodes.get_slice() {
+            let node = cell.get_mut();
+            node.connections = node.connections.iter().filter(|connection| {
+                !removed.contains(&node.index) && !removed.contains(&connection.index)
+            }).cloned().collect();
+        }
+        nodes
+    }
+
+    fn find_path(nodes : &ParallelList<Node>, restrictions : &[(Index, Index, Index)], start : Index, end : Index) -> Option<Box<[Index]>> {
+        let restrictions : Vec<TurnRestriction> = restrictions.iter().enumerate()
+            .map(|(id, (from, via, to))| TurnRestriction { id : id as Index, from : *from, via : *via, to : *to }).collect();
+        let graph = TurnGraph::new(nodes.get_slice(), &restrictions);
+        let outage_clock = OutageClock::new(Utc::now(), SOUTH_AFRICA_UTC_OFFSET);
+        EdgeSearch::new(graph.len()).find_path(&graph, nodes.get_slice(), &SearchMethod::FASTEST.cost_model(), &outage_clock, start, end)
+            .map(|(path, _, _)| path)
+    }
+
+    #[test]
+    fn restricted_turns_are_never_taken() {
+        let nodes = grid(3, 3, 0);
+        assert_eq!(*find_path(&nodes, &[], 0, 2).unwrap(), [2, 1, 0]);
+        let path = find_path(&nodes, &[(0, 1, 2)], 0, 2).unwrap();
+        assert!(path.windows(3).all(|turn| turn != [2, 1, 0]), "{path:?}");
+    }
+
+    #[test]
+    fn u_turns_only_at_dead_ends() {
+        let nodes = junction();
+        assert_eq!(*find_path(&nodes, &[(0, 1, 2)], 0, 2).unwrap(), [2, 1, 5, 1, 0]);
+        // Turning back at 2 would be quicker, but only 3 is a dead end.
+        assert_eq!(*find_path(&nodes, &[(5, 1, 0)], 5, 0).unwrap(), [0, 1, 2, 3, 2, 1, 5]);
+        assert!(find_path(&nodes, &[(0, 1, 2), (5, 1, 2)], 0, 2).is_none());
+    }
+}
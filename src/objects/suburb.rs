@@ -5,17 +5,52 @@ use std::simd::num::SimdFloat;
 use crate::loader::{read_f64, read_i32, skip_string};
 use crate::new_pos_slice;
 use crate::objects::boundary::Boundary;
-use crate::traits::{ByteConvertable, Indexable};
+use crate::traits::{Bounded, ByteConvertable, Indexable};
 use crate::types::{Index, Pos};
 
+/// Area enclosed by a closed ring of points, by the shoelace formula. Added up in `f64` from the first point, since the
+/// coordinates are large next to their differences.
+fn polygon_area(x_points : &[Pos], y_points : &[Pos]) -> Pos {
+    let (Some(origin_x), Some(origin_y)) = (x_points.first(), y_points.first()) else {
+        return 0f64 as Pos;
+    };
+    let relative = |point : usize| (x_points[point] as f64 - *origin_x as f64, y_points[point] as f64 - *origin_y as f64);
+    let twice_area : f64 = (1..x_points.len().min(y_points.len())).map(|point| {
+        let (ax, ay) = relative(point - 1);
+        let (bx, by) = relative(point);
+        ax * by - bx * ay
+    }).sum();
+    (twice_area.abs() / 2.0) as Pos
+}
+
 pub struct Suburb {
     pub id : Index,
     pub boundary : Boundary,
+    /// Area enclosed by the ring, worked out once when the suburb is made.
+    pub area : Pos,
     pub x_points : Box<[Pos]>,
     pub y_points : Box<[Pos]>,
 }
 
 impl Suburb {
+    /// Creates a suburb from a closed ring of points, where the last point repeats the first, bounded by the points.
+    pub fn from_points(id : Index, x_points : Box<[Pos]>, y_points : Box<[Pos]>) -> Self {
+        let mut corner_min = Simd::splat(Pos::MAX);
+        let mut corner_max = Simd::splat(Pos::MIN);
+        for (x, y) in x_points.iter().zip(y_points.iter()) {
+            let position = Simd::from_array([*x, *y]);
+            corner_min = corner_min.simd_min(position);
+            corner_max = corner_max.simd_max(position);
+        }
+        Suburb {
+            id,
+            boundary : Boundary { corner_max, corner_min },
+            area : polygon_area(&x_points, &y_points),
+            x_points,
+            y_points
+        }
+    }
+
     /**
      * Taken from and translated from the Even-Odd rule algorithm found on Wikipedia, using SIMD where possible.
      * <br>https://en.wikipedia.org/wiki/Even-odd_rule</br>
@@ -59,6 +94,13 @@ impl Suburb {
     }
 }
 
+impl Bounded for Suburb {
+    #[inline]
+    fn boundary(&self) -> &Boundary {
+        &self.boundary
+    }
+}
+
 impl Indexable for Suburb {
     fn index(&self) -> Index { self.id }
 }
@@ -87,9 +129,45 @@ impl ByteConvertable for Suburb {
         };
         Suburb {
             id,
+            area : polygon_area(&x_points, &y_points),
             x_points,
             y_points,
             boundary
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_suburb;
+    use crate::objects::util::r_tree::RTree;
+
+    fn ring(id : Index, points : &[(Pos, Pos)]) -> Suburb {
+        let closed = points.iter().chain(points.first());
+        Suburb::from_points(id, closed.clone().map(|point| point.0).collect(), closed.map(|point| point.1).collect())
+    }
+
+    #[test]
+    fn area_is_that_of_the_polygon() {
+        assert_eq!(ring(1, &[(2.0, 2.0), (6.0, 2.0), (6.0, 5.0), (2.0, 5.0)]).area, 12.0);
+        assert_eq!(ring(2, &[(2.0, 5.0), (6.0, 5.0), (6.0, 2.0), (2.0, 2.0)]).area, 12.0);
+        assert_eq!(ring(3, &[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]).area, 6.0);
+        let strip = ring(4, &[(28.0, -26.0), (28.01, -25.99), (28.01, -25.989), (28.0, -25.999)]);
+        assert!((strip.area - 1e-5).abs() < 1e-7, "{}", strip.area);
+    }
+
+    #[test]
+    fn finds_the_suburb_with_the_smallest_polygon() {
+        // A thin strip along the diagonal, with a far larger bounding box than the square but less area.
+        let suburbs = [
+            ring(1, &[(0.0, 0.0), (10.0, 9.8), (10.0, 10.0), (0.0, 0.2)]),
+            ring(2, &[(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)]),
+            ring(3, &[(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)])
+        ];
+        let tree = RTree::new(&suburbs);
+        assert_eq!(find_suburb(&tree, &Simd::from_array([5.0, 5.0])).map(|suburb| suburb.id), Some(1));
+        assert_eq!(find_suburb(&tree, &Simd::from_array([4.5, 5.5])).map(|suburb| suburb.id), Some(2));
+        assert!(find_suburb(&tree, &Simd::from_array([8.0, 2.0])).is_none());
+    }
+}
@@ -1,13 +1,20 @@
 use crate::loader::{read_f64, read_i32};
+use crate::objects::load_shedding::OutageInterval;
 use crate::traits::{ByteConvertable, Indexable, Positional};
 use crate::types::{Flag, Index, Pos};
 use std::simd::Simd;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct TrafficLight {
     pub id : Index,
     pub position: Simd<Pos, 2>,
-    pub flag : Flag
+    pub flag : Flag,
+    /// Suburb the traffic light lies in, as found by `compute`, or 0 when it is in none.
+    pub suburb : Index,
+    /// Load shedding block of the suburb, once a schedule covering it is loaded.
+    pub block : Option<Index>,
+    /// When the traffic light is out, sorted and disjoint. Takes over from the hourly bits of `flag` when not empty.
+    pub outages : Box<[OutageInterval]>
 }
 
 impl Indexable for TrafficLight {
@@ -35,7 +42,10 @@ impl ByteConvertable for TrafficLight {
         Self {
             id : id as Index,
             position : Simd::from_array([x, y]),
-            flag : flag as Flag
+            flag : flag as Flag,
+            suburb : 0,
+            block : None,
+            outages : Box::new([])
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod parallel_list;
 pub mod stop_watch;
 pub mod super_cell;
-pub mod quad_tree;
\ No newline at end of file
+pub mod quad_tree;
+pub mod r_tree;
\ No newline at end of file
@@ -37,12 +37,12 @@ impl <T> ParallelList<T> {
     }
     #[inline]
     pub fn get_slice(&self) -> &[SuperCell<T>] {
-        unsafe { MaybeUninit::slice_assume_init_ref(self.data.get_mut().as_slice()) }
+        unsafe { self.data.get_mut().as_slice().assume_init_ref() }
     }
 
     #[inline]
     pub fn as_slice(&self) -> &[T] {
-        unsafe {  &*(MaybeUninit::slice_assume_init_ref(self.data.get_mut().as_slice()) as *const [SuperCell<T>] as *const [T]) }
+        unsafe {  &*(self.data.get_mut().as_slice().assume_init_ref() as *const [SuperCell<T>] as *const [T]) }
     }
 
     pub fn add(&mut self, value : T) {
@@ -56,12 +56,12 @@ impl <T> ParallelList<T> {
 impl <T : Sync + Send> ParallelList<T> {
     #[inline]
     pub fn get_slice_mut(&self) -> &mut [SuperCell<T>] {
-        unsafe { MaybeUninit::slice_assume_init_mut(self.data.get_mut().as_parallel_slice_mut()) }
+        unsafe { self.data.get_mut().as_parallel_slice_mut().assume_init_mut() }
     }
 
     #[inline]
     pub fn as_slice_mut(&mut self) -> &mut [T] {
-        unsafe { &mut *(MaybeUninit::slice_assume_init_mut(self.data.get_mut().as_parallel_slice_mut()) as *mut [SuperCell<T>] as *mut [T])}
+        unsafe { &mut *(self.data.get_mut().as_parallel_slice_mut().assume_init_mut() as *mut [SuperCell<T>] as *mut [T])}
     }
 }
 
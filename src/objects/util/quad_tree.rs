@@ -1,11 +1,128 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use std::simd::Simd;
+use std::simd::prelude::SimdFloat;
 use std::ops::{Div, Sub};
+use crate::{distance, MULTIPLIER};
 use crate::objects::boundary::Boundary;
 use crate::traits::Positional;
 use crate::types::Pos;
 
 const MAX_CAPACITY : usize = 256;
+/// Quadrants this deep are not split any further. They keep every item that lands in them, past `MAX_CAPACITY`, as an
+/// overflow bucket for points too close together to ever be told apart, such as duplicated coordinates.
 const MAX_DEPTH : i8 = 32;
+/// Items a quadrant's children may hold together before a removal merges them back into it. Lower than
+/// `MAX_CAPACITY` so that adding and removing around the limit does not split and merge the same quadrant every time.
+const MERGE_CAPACITY : usize = MAX_CAPACITY / 2;
+
+/// The boundaries of the four quadrants of `boundary`, in the order items are placed in them: top left, top right,
+/// bottom left and bottom right.
+fn split(boundary : &Boundary) -> [Boundary; 4] {
+    let corner_min_simd = &boundary.corner_min;
+    let corner_max_simd = &boundary.corner_max;
+    let center_simd = &center(boundary);
+    let corner_min_array = corner_min_simd.as_array();
+    let center_array = center_simd.as_array();
+    let corner_max_array = corner_max_simd.as_array();
+    [
+        Boundary {
+            corner_min: Simd::from_array([corner_min_array[0], center_array[1]]),
+            corner_max: Simd::from_array([center_array[0], corner_max_array[1]])
+        },
+        Boundary {
+            corner_min: *center_simd,
+            corner_max: *corner_max_simd
+        },
+        Boundary {
+            corner_min: *corner_min_simd,
+            corner_max: *center_simd
+        },
+        Boundary {
+            corner_min: Simd::from_array([center_array[0], corner_min_array[1]]),
+            corner_max: Simd::from_array([corner_max_array[0], center_array[1]])
+        }
+    ]
+}
+
+#[inline]
+fn center(boundary : &Boundary) -> Simd<Pos, 2> {
+    boundary.corner_max.sub((boundary.corner_max - boundary.corner_min).div(Simd::from_array([2f64 as Pos, 2f64 as Pos])))
+}
+
+/// The Morton digit of `position` in the quadrant around `center`: the position in `split` of the first quadrant
+/// containing it. Left of the center or on it comes before right, and above or on it before below.
+#[inline]
+fn morton_digit(center : &Simd<Pos, 2>, position : &Simd<Pos, 2>) -> u8 {
+    ((position[1] < center[1]) as u8) << 1 | (position[0] > center[0]) as u8
+}
+
+/// How many items went into a tree, and how many were left out because their position is not a finite number.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct InsertionReport {
+    pub inserted : usize,
+    pub dropped : usize
+}
+
+/// A quadrant or an item waiting to be visited by a nearest neighbour search.
+enum Candidate<'tree, 'life, T : Positional> {
+    Quadrant(&'tree QuadTree<'life, T>),
+    Item(&'life T)
+}
+
+/// A candidate with its distance to the point searched from, ordered so that `BinaryHeap` pops the closest first.
+struct Visit<'tree, 'life, T : Positional> {
+    distance : Pos,
+    candidate : Candidate<'tree, 'life, T>
+}
+
+impl <T : Positional> PartialEq for Visit<'_, '_, T> {
+    fn eq(&self, other : &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl <T : Positional> Eq for Visit<'_, '_, T> {}
+
+impl <T : Positional> PartialOrd for Visit<'_, '_, T> {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl <T : Positional> Ord for Visit<'_, '_, T> {
+    fn cmp(&self, other : &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+/// Iterator over the items of a `QuadTree` inside a boundary, which only opens the quadrants overlapping it.
+pub struct BoundaryQuery<'tree, 'life, T : Positional> {
+    boundary : Boundary,
+    quadrants : Vec<&'tree QuadTree<'life, T>>,
+    items : std::slice::Iter<'tree, &'life T>
+}
+
+impl <'life, T : Positional> Iterator for BoundaryQuery<'_, 'life, T> {
+    type Item = &'life T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                if self.boundary.contains(item.position()) {
+                    return Some(*item);
+                }
+                continue;
+            }
+            let quadrant = self.quadrants.pop()?;
+            if quadrant.has_children {
+                self.quadrants.extend(quadrant.children().filter(|child| child.boundary.does_overlap(&self.boundary)));
+            } else {
+                self.items = quadrant.data.iter();
+            }
+        }
+    }
+}
 
 pub struct QuadTree<'life, T : Positional> {
     pub top_left : Box<Option<QuadTree<'life, T>>>,
@@ -96,6 +213,199 @@ impl <'life, T : Positional> QuadTree<'life, T> {
         None
     }
     
+    /// Distance in metres from `point` to the closest point of this quadrant, 0 when it lies inside.
+    #[inline]
+    fn min_distance(&self, point : &Simd<Pos, 2>) -> Pos {
+        distance(point, &point.simd_clamp(self.boundary.corner_min, self.boundary.corner_max))
+    }
+
+    fn children(&self) -> impl Iterator<Item = &QuadTree<'life, T>> {
+        [&self.top_left, &self.top_right, &self.bottom_left, &self.bottom_right].into_iter()
+            .filter_map(|child| child.as_ref().as_ref())
+    }
+
+    /// The items inside `boundary`, edges included, in no particular order.
+    pub fn query_boundary(&self, boundary : Boundary) -> BoundaryQuery<'_, 'life, T> {
+        BoundaryQuery {
+            quadrants : if self.boundary.does_overlap(&boundary) { vec![self] } else { Vec::new() },
+            boundary,
+            items : [].iter()
+        }
+    }
+
+    /// The items at most `metres` away from `center`, in no particular order.
+    pub fn query_radius(&self, center : Simd<Pos, 2>, metres : Pos) -> impl Iterator<Item = &'life T> + '_ {
+        let extent = Simd::splat(metres) / MULTIPLIER;
+        self.query_boundary(Boundary {
+            corner_max : center + extent,
+            corner_min : center - extent
+        }).filter(move |item| distance(&center, item.position()) <= metres)
+    }
+
+    /// Up to `count` items closest to `point`, closest first, leaving out those further than `max_radius` metres.
+    /// Quadrants are visited best first by their distance to the point, so that items just across the border of the
+    /// quadrant the point lies in are found too, and quadrants further away than the items found are never opened.
+    pub fn nearest(&self, point : &Simd<Pos, 2>, count : usize, max_radius : Option<Pos>) -> Vec<&'life T> {
+        let max_radius = max_radius.unwrap_or(Pos::INFINITY);
+        let mut nearest = Vec::with_capacity(count.min(MAX_CAPACITY));
+        let mut queue = BinaryHeap::new();
+        queue.push(Visit { distance : self.min_distance(point), candidate : Candidate::Quadrant(self) });
+        while nearest.len() < count {
+            let Some(Visit { distance : visit_distance, candidate }) = queue.pop() else {
+                break;
+            };
+            if visit_distance > max_radius {
+                break;
+            }
+            match candidate {
+                Candidate::Item(item) => nearest.push(item),
+                Candidate::Quadrant(quadrant) if quadrant.has_children => {
+                    queue.extend(quadrant.children().map(|child| Visit {
+                        distance : child.min_distance(point),
+                        candidate : Candidate::Quadrant(child)
+                    }));
+                }
+                Candidate::Quadrant(quadrant) => {
+                    queue.extend(quadrant.data.iter().map(|item| Visit {
+                        distance : distance(point, item.position()),
+                        candidate : Candidate::Item(*item)
+                    }));
+                }
+            }
+        }
+        nearest
+    }
+
+    /// The item closest to `point` within `max_radius` metres, if there is any.
+    #[inline]
+    pub fn find_nearest(&self, point : &Simd<Pos, 2>, max_radius : Option<Pos>) -> Option<&'life T> {
+        self.nearest(point, 1, max_radius).pop()
+    }
+
+    /// Builds a tree over `items` in one go. The items are sorted by Morton code, one digit for every level the tree
+    /// needs, so that every quadrant gets a contiguous run of them and is only split once, instead of items being
+    /// pushed down one at a time. Items outside `boundary` are inserted afterwards, growing the tree to fit them.
+    pub fn from_items(boundary : Boundary, items : impl IntoIterator<Item = &'life T>) -> (QuadTree<'life, T>, InsertionReport) {
+        let (mut inside, outside) : (Vec<&'life T>, Vec<&'life T>) = items.into_iter()
+            .partition(|item| boundary.contains(item.position()));
+        let mut report = InsertionReport {
+            inserted : inside.len(),
+            dropped : 0
+        };
+        let mut tree = Self::bulk_load(boundary, 0, &mut inside);
+        for item in outside {
+            if tree.insert(item) {
+                report.inserted += 1;
+            } else {
+                report.dropped += 1;
+            }
+        }
+        (tree, report)
+    }
+
+    fn bulk_load(boundary : Boundary, depth : i8, items : &mut [&'life T]) -> QuadTree<'life, T> {
+        let mut tree = QuadTree::new(boundary, depth);
+        if items.len() <= MAX_CAPACITY || depth >= MAX_DEPTH {
+            tree.data = items.to_vec();
+        } else {
+            let center = center(&tree.boundary);
+            items.sort_unstable_by_key(|item| morton_digit(&center, item.position()));
+            let mut rest = items;
+            let mut digit = 0;
+            let [top_left, top_right, bottom_left, bottom_right] = split(&tree.boundary).map(|child_boundary| {
+                let split_at = rest.partition_point(|item| morton_digit(&center, item.position()) <= digit);
+                let (child_items, remaining) = std::mem::take(&mut rest).split_at_mut(split_at);
+                rest = remaining;
+                digit += 1;
+                Self::bulk_load(child_boundary, depth + 1, child_items)
+            });
+            tree.top_left = Box::new(Some(top_left));
+            tree.top_right = Box::new(Some(top_right));
+            tree.bottom_left = Box::new(Some(bottom_left));
+            tree.bottom_right = Box::new(Some(bottom_right));
+            tree.has_children = true;
+        }
+        tree
+    }
+
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut QuadTree<'life, T>> {
+        [&mut self.top_left, &mut self.top_right, &mut self.bottom_left, &mut self.bottom_right].into_iter()
+            .filter_map(|child| child.as_mut().as_mut())
+    }
+
+    /// Removes `data`, the very item rather than an equal one, from the tree. Quadrants whose children end up holding
+    /// few enough items between them take those items back and drop the children. `false` when it was not in the tree.
+    pub fn remove(&mut self, data : &T) -> bool {
+        self.remove_at(data.position(), data)
+    }
+
+    /// Removes `data` from the quadrants containing `position`, which is where it was added.
+    fn remove_at(&mut self, position : &Simd<Pos, 2>, data : &T) -> bool {
+        if !self.contains(position) {
+            return false;
+        }
+        if !self.has_children {
+            return match self.data.iter().position(|item| std::ptr::eq(*item, data)) {
+                Some(item_index) => {
+                    self.data.swap_remove(item_index);
+                    true
+                }
+                None => false
+            };
+        }
+        let removed = self.children_mut().any(|child| child.remove_at(position, data));
+        if removed {
+            self.merge();
+        }
+        removed
+    }
+
+    fn merge(&mut self) {
+        let mut count = 0;
+        for child in self.children() {
+            if child.has_children {
+                return;
+            }
+            count += child.data.len();
+        }
+        if count > MERGE_CAPACITY {
+            return;
+        }
+        let mut data = Vec::with_capacity(count);
+        for child in self.children_mut() {
+            data.append(&mut child.data);
+        }
+        self.data = data;
+        *self.top_left = None;
+        *self.top_right = None;
+        *self.bottom_left = None;
+        *self.bottom_right = None;
+        self.has_children = false;
+    }
+
+    /// The quadrant without children that holds `data`, looking among those containing `position`.
+    fn find_leaf(&self, position : &Simd<Pos, 2>, data : &T) -> Option<&QuadTree<'life, T>> {
+        if !self.contains(position) {
+            return None;
+        }
+        if self.has_children {
+            self.children().find_map(|child| child.find_leaf(position, data))
+        } else {
+            self.data.iter().any(|item| std::ptr::eq(*item, data)).then_some(self)
+        }
+    }
+
+    /// Updates the tree for `data` having moved from `old_position` to where it is now. It stays where it is when its
+    /// quadrant still contains it, and is removed and inserted again otherwise. `false` when it was not in the tree,
+    /// or has moved to a position that is not a finite number, in which case it is no longer in the tree.
+    pub fn move_data(&mut self, data : &'life T, old_position : &Simd<Pos, 2>) -> bool {
+        match self.find_leaf(old_position, data) {
+            Some(leaf) if leaf.contains(data.position()) => true,
+            Some(_) => self.remove_at(old_position, data) && self.insert(data),
+            None => false
+        }
+    }
+
     #[inline]
     fn get_top_left_node(&mut self) -> &mut QuadTree<'life, T> {
         self.top_left.as_mut().as_mut().expect("Had Children, but no top left tree")
@@ -113,6 +423,71 @@ impl <'life, T : Positional> QuadTree<'life, T> {
         self.bottom_right.as_mut().as_mut().expect("Had Children, but no bottom right tree")
     }
 
+    /// Adds `data` to this tree, growing it until it contains the position of `data` when it lies outside. Only to be
+    /// called on the root, since it replaces this quadrant with a larger one. `false` when the position is not a finite
+    /// number.
+    pub fn insert(&mut self, data : &'life T) -> bool {
+        let position = data.position();
+        if !position.is_finite().all() {
+            return false;
+        }
+        while !self.contains(position) {
+            self.expand(position);
+        }
+        self.add_data(data)
+    }
+
+    /// Doubles this quadrant in both directions towards `towards`, or grows it by as much as `towards` lies outside it
+    /// when that is more, making what it was one of its four children. The new quadrants share their borders with the
+    /// old one exactly, so that no point can fall between them.
+    fn expand(&mut self, towards : &Simd<Pos, 2>) {
+        let corner_min = self.boundary.corner_min;
+        let corner_max = self.boundary.corner_max;
+        // At least as far as `towards` lies outside, for quadrants too small to reach it by doubling.
+        let size = (corner_max - corner_min).simd_max((towards - corner_min).abs().simd_min((towards - corner_max).abs()));
+        let left = towards[0] < corner_min[0];
+        let below = towards[1] < corner_min[1];
+        let (left_x, right_x) = if left {
+            ((corner_min[0] - size[0], corner_min[0]), (corner_min[0], corner_max[0]))
+        } else {
+            ((corner_min[0], corner_max[0]), (corner_max[0], corner_max[0] + size[0]))
+        };
+        let (bottom_y, top_y) = if below {
+            ((corner_min[1] - size[1], corner_min[1]), (corner_min[1], corner_max[1]))
+        } else {
+            ((corner_min[1], corner_max[1]), (corner_max[1], corner_max[1] + size[1]))
+        };
+        let quadrant = |x : (Pos, Pos), y : (Pos, Pos)| Boundary {
+            corner_min : Simd::from_array([x.0, y.0]),
+            corner_max : Simd::from_array([x.1, y.1])
+        };
+        let depth = self.depth;
+        let mut old = std::mem::replace(self, QuadTree::new(quadrant((left_x.0, right_x.1), (bottom_y.0, top_y.1)), depth));
+        old.deepen();
+        let mut children = [
+            quadrant(left_x, top_y),
+            quadrant(right_x, top_y),
+            quadrant(left_x, bottom_y),
+            quadrant(right_x, bottom_y)
+        ].map(|boundary| Some(QuadTree::new(boundary, depth + 1)));
+        // The old quadrant ends up on the side away from `towards`, in the order of `split`.
+        children[(!below as usize) << 1 | left as usize] = Some(old);
+        let [top_left, top_right, bottom_left, bottom_right] = children;
+        *self.top_left = top_left;
+        *self.top_right = top_right;
+        *self.bottom_left = bottom_left;
+        *self.bottom_right = bottom_right;
+        self.has_children = true;
+    }
+
+    /// Moves this quadrant and everything below it one level down.
+    fn deepen(&mut self) {
+        self.depth = self.depth.saturating_add(1);
+        for child in self.children_mut() {
+            child.deepen();
+        }
+    }
+
     pub fn add_data(&mut self, data : &'life T) -> bool {
         let position = data.position();
         if self.contains(position) {
@@ -135,14 +510,12 @@ impl <'life, T : Positional> QuadTree<'life, T> {
                 }
                 panic!("Trying to add a point that was inside a tree that had children, but it was not inside any children!");
             } else {
-                return if self.data.len() < MAX_CAPACITY {
+                return if self.data.len() < MAX_CAPACITY || self.depth >= MAX_DEPTH {
                     self.data.push(data);
                     true
-                } else if self.depth < MAX_DEPTH {
+                } else {
                     self.sub_divide();
                     self.add_data(data)
-                } else {
-                    false
                 };
             }
         }
@@ -150,30 +523,8 @@ impl <'life, T : Positional> QuadTree<'life, T> {
     }
 
     pub fn sub_divide(&mut self) {
-        let corner_min_simd = &self.boundary.corner_min;
-        let corner_max_simd = &self.boundary.corner_max;
-        let center_simd = &corner_max_simd.sub((corner_max_simd - corner_min_simd).div(Simd::from_array([2f64 as Pos, 2f64 as Pos]))) ;
-        let corner_min_array = corner_min_simd.as_array();
-        let center_array = center_simd.as_array();
-        let corner_max_array = corner_max_simd.as_array();
-
         let new_depth = self.depth + 1;
-        let top_left_boundary = Boundary {
-            corner_min: Simd::from_array([corner_min_array[0], center_array[1]]),
-            corner_max: Simd::from_array([center_array[0], corner_max_array[1]])
-        };
-        let bottom_right_boundary = Boundary {
-            corner_min: Simd::from_array([center_array[0], corner_min_array[1]]),
-            corner_max: Simd::from_array([corner_max_array[0], center_array[1]])
-        };
-        let top_right_boundary = Boundary {
-            corner_min: *center_simd,
-            corner_max: *corner_max_simd
-        };
-        let bottom_left_boundary = Boundary {
-            corner_min: *corner_min_simd,
-            corner_max: *center_simd
-        };
+        let [top_left_boundary, top_right_boundary, bottom_left_boundary, bottom_right_boundary] = split(&self.boundary);
         let mut top_left = QuadTree::new(top_left_boundary, new_depth);
         let mut top_right = QuadTree::new(top_right_boundary, new_depth);
         let mut bottom_left = QuadTree::new(bottom_left_boundary, new_depth);
@@ -202,4 +553,158 @@ impl <'life, T : Positional> QuadTree<'life, T> {
         
         self.has_children = true;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::util::super_cell::SuperCell;
+
+    struct Point {
+        position : Simd<Pos, 2>
+    }
+
+    impl Positional for Point {
+        fn position(&self) -> &Simd<Pos, 2> {
+            &self.position
+        }
+    }
+
+    fn square(min : Pos, max : Pos) -> Boundary {
+        Boundary {
+            corner_max : Simd::splat(max),
+            corner_min : Simd::splat(min)
+        }
+    }
+
+    /// `count` points spread over the unit square, the same ones on every run.
+    fn points(count : usize) -> Vec<SuperCell<Point>> {
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as Pos / (1 << 24) as Pos
+        };
+        (0..count).map(|_| SuperCell::new(Point { position : Simd::from_array([next(), next()]) })).collect()
+    }
+
+    fn tree_of(points : &[SuperCell<Point>]) -> QuadTree<'_, SuperCell<Point>> {
+        let mut tree = QuadTree::new(square(0.0, 1.0), 0);
+        for point in points {
+            assert!(tree.insert(point));
+        }
+        tree
+    }
+
+    fn addresses<'a>(items : impl Iterator<Item = &'a SuperCell<Point>>) -> Vec<usize> {
+        let mut addresses : Vec<usize> = items.map(|item| item as *const SuperCell<Point> as usize).collect();
+        addresses.sort_unstable();
+        addresses
+    }
+
+    /// Checks that boundary queries return the same items as looking through every point still in the tree.
+    fn assert_matches_scan(tree : &QuadTree<SuperCell<Point>>, points : &[SuperCell<Point>], present : &[bool]) {
+        for (min, max) in [(-10.0, 10.0), (0.0, 0.5), (0.25, 0.75), (0.5, 1.0), (0.9, 0.95), (2.0, 3.0)] {
+            let boundary = square(min, max);
+            let scanned = points.iter().zip(present).filter(|(point, present)| **present && boundary.contains(point.position()))
+                .map(|(point, _)| point);
+            assert_eq!(addresses(tree.query_boundary(square(min, max))), addresses(scanned), "{boundary}");
+        }
+    }
+
+    #[test]
+    fn removing_merges_children_back() {
+        let points = points(MAX_CAPACITY + 44);
+        let mut tree = tree_of(&points);
+        assert!(tree.has_children);
+        let mut present = vec![true; points.len()];
+        for (index, point) in points.iter().enumerate().skip(MERGE_CAPACITY) {
+            assert!(tree.has_children);
+            assert!(tree.remove(point));
+            assert!(!tree.remove(point));
+            present[index] = false;
+            assert_matches_scan(&tree, &points, &present);
+        }
+        assert!(!tree.has_children);
+        assert_eq!(tree.data.len(), MERGE_CAPACITY);
+        assert!(tree.top_left.is_none() && tree.top_right.is_none() && tree.bottom_left.is_none() && tree.bottom_right.is_none());
+    }
+
+    #[test]
+    fn moves_within_and_across_quadrants() {
+        let points = points(MAX_CAPACITY * 3);
+        let mut tree = tree_of(&points);
+        let mut present = vec![true; points.len()];
+        for (index, point) in points.iter().enumerate() {
+            let old_position = *point.position();
+            let leaf = tree.find_leaf(&old_position, point).expect("Point is not in the tree");
+            let old_leaf = (leaf.boundary.corner_min, leaf.boundary.corner_max);
+            let nudged = old_position + Simd::splat(1e-6);
+            let within = index % 2 == 0 && leaf.contains(&nudged);
+            point.get_mut().position = if within { nudged } else { Simd::splat(1.0) - old_position };
+            assert!(tree.move_data(point, &old_position));
+            let leaf = tree.find_leaf(point.position(), point).expect("Moved point is not in the tree");
+            if within {
+                assert_eq!((leaf.boundary.corner_min, leaf.boundary.corner_max), old_leaf);
+            }
+        }
+        assert_matches_scan(&tree, &points, &present);
+
+        let point = &points[0];
+        let old_position = *point.position();
+        point.get_mut().position = Simd::splat(Pos::NAN);
+        assert!(!tree.move_data(point, &old_position));
+        assert!(!tree.move_data(point, &old_position));
+        present[0] = false;
+        assert_matches_scan(&tree, &points, &present);
+    }
+
+    #[test]
+    fn inserting_outside_expands_the_root() {
+        let inside = MAX_CAPACITY * 2;
+        let mut points = points(inside);
+        points.extend([[3.5, -2.0], [-40.0, 0.5], [1.0, 1e-7], [Pos::NAN, 0.5], [0.5, Pos::INFINITY]]
+            .map(|position| SuperCell::new(Point { position : Simd::from_array(position) })));
+        let mut tree = tree_of(&points[.. inside]);
+        for point in &points[inside .. inside + 3] {
+            let depth = tree.depth;
+            assert!(tree.insert(point));
+            assert!(tree.contains(point.position()));
+            assert_eq!(tree.depth, depth);
+            assert!(std::ptr::eq(tree.find_nearest(point.position(), None).expect("Nothing found"), point));
+        }
+        for point in &points[inside + 3 ..] {
+            assert!(!tree.insert(point));
+        }
+        let mut present = vec![true; points.len()];
+        present[inside + 3 ..].fill(false);
+        assert_matches_scan(&tree, &points, &present);
+
+        let (tree, report) = QuadTree::from_items(square(0.0, 1.0), points.iter());
+        assert_eq!(report, InsertionReport { inserted : inside + 3, dropped : 2 });
+        assert_matches_scan(&tree, &points, &present);
+    }
+
+    #[test]
+    fn points_at_max_depth_overflow() {
+        let duplicates : Vec<SuperCell<Point>> = (0 .. MAX_CAPACITY * 2 + 1)
+            .map(|_| SuperCell::new(Point { position : Simd::from_array([0.3, 0.7]) }))
+            .collect();
+        let others = points(MAX_CAPACITY / 4);
+        let mut tree = tree_of(&others);
+        for duplicate in &duplicates {
+            assert!(tree.insert(duplicate));
+        }
+        let leaf = tree.find_leaf(duplicates[0].position(), &duplicates[0]).expect("Duplicate is not in the tree");
+        assert_eq!(leaf.depth, MAX_DEPTH);
+        assert!(leaf.data.len() >= duplicates.len());
+        let found = addresses(tree.query_radius(*duplicates[0].position(), 0.0).filter(|item| item.position() == duplicates[0].position()));
+        assert_eq!(found, addresses(duplicates.iter()));
+        assert_eq!(tree.nearest(duplicates[0].position(), duplicates.len(), Some(0.0)).len(), duplicates.len());
+
+        for duplicate in &duplicates {
+            assert!(tree.remove(duplicate));
+        }
+        assert!(!tree.has_children);
+        assert_eq!(addresses(tree.data.iter().copied()), addresses(others.iter()));
+    }
+}
@@ -0,0 +1,187 @@
+use std::simd::Simd;
+use std::simd::prelude::SimdFloat;
+use crate::objects::boundary::Boundary;
+use crate::traits::Bounded;
+use crate::types::Pos;
+
+/// Most children a node of the tree holds, items for a leaf and nodes otherwise.
+const NODE_CAPACITY : usize = 16;
+
+struct RTreeNode {
+    boundary : Boundary,
+    /// Position of the first child in `RTree::items` for a leaf, or in `RTree::nodes` otherwise.
+    first : usize,
+    count : usize,
+    is_leaf : bool
+}
+
+/// A static R-tree over the boundaries of its items, packed with Sort-Tile-Recursive: the items are sorted by the x of
+/// their centers into vertical slices, and every slice by y into runs of `NODE_CAPACITY`, which become the leaves.
+/// The levels above are packed the same way from the boundaries of the level below. Every node ends up close to full
+/// and overlapping its neighbours little, so that a query opens few nodes.
+pub struct RTree<'life, T : Bounded> {
+    /// The items in the order of the leaves, every leaf holding a contiguous run.
+    items : Vec<&'life T>,
+    /// The nodes level by level from the leaves up, the root last.
+    nodes : Vec<RTreeNode>
+}
+
+#[inline]
+fn center(boundary : &Boundary) -> Simd<Pos, 2> {
+    (boundary.corner_min + boundary.corner_max) / Simd::splat(2f64 as Pos)
+}
+
+/// The smallest boundary around all of `boundaries`.
+fn union<'a>(boundaries : impl Iterator<Item = &'a Boundary>) -> Boundary {
+    let mut corner_min = Simd::splat(Pos::MAX);
+    let mut corner_max = Simd::splat(Pos::MIN);
+    for boundary in boundaries {
+        corner_min = corner_min.simd_min(boundary.corner_min);
+        corner_max = corner_max.simd_max(boundary.corner_max);
+    }
+    Boundary { corner_max, corner_min }
+}
+
+/// Orders `entries` so that every run of `NODE_CAPACITY` of them makes one node, by Sort-Tile-Recursive.
+fn pack<E>(entries : &mut [E], boundary : impl Fn(&E) -> &Boundary) {
+    let node_count = entries.len().div_ceil(NODE_CAPACITY);
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count.max(1) * NODE_CAPACITY;
+    entries.sort_unstable_by(|a, b| center(boundary(a))[0].total_cmp(&center(boundary(b))[0]));
+    for slice in entries.chunks_mut(slice_size) {
+        slice.sort_unstable_by(|a, b| center(boundary(a))[1].total_cmp(&center(boundary(b))[1]));
+    }
+}
+
+impl <'life, T : Bounded> RTree<'life, T> {
+    pub fn new(items : impl IntoIterator<Item = &'life T>) -> RTree<'life, T> {
+        let mut items : Vec<&'life T> = items.into_iter().collect();
+        pack(&mut items, |item| item.boundary());
+        let mut nodes : Vec<RTreeNode> = items.chunks(NODE_CAPACITY).enumerate().map(|(chunk_index, chunk)| RTreeNode {
+            boundary : union(chunk.iter().map(|item| item.boundary())),
+            first : chunk_index * NODE_CAPACITY,
+            count : chunk.len(),
+            is_leaf : true
+        }).collect();
+        let mut level_start = 0;
+        while nodes.len() - level_start > 1 {
+            let level_end = nodes.len();
+            pack(&mut nodes[level_start..level_end], |node| &node.boundary);
+            let parents : Vec<RTreeNode> = nodes[level_start..level_end].chunks(NODE_CAPACITY).enumerate()
+                .map(|(chunk_index, chunk)| RTreeNode {
+                    boundary : union(chunk.iter().map(|node| &node.boundary)),
+                    first : level_start + chunk_index * NODE_CAPACITY,
+                    count : chunk.len(),
+                    is_leaf : false
+                }).collect();
+            nodes.extend(parents);
+            level_start = level_end;
+        }
+        RTree { items, nodes }
+    }
+
+    /// The items whose boundary passes `test`, opening only the nodes whose boundary passes it too. `test` has to pass
+    /// every boundary around one that passes.
+    fn query<F : Fn(&Boundary) -> bool>(&self, test : F) -> RTreeQuery<'_, 'life, T, F> {
+        RTreeQuery {
+            tree : self,
+            nodes : self.nodes.len().checked_sub(1).filter(|root| test(&self.nodes[*root].boundary)).into_iter().collect(),
+            items : [].iter(),
+            test
+        }
+    }
+
+    /// The items whose boundary contains `point`, edges included, in no particular order.
+    pub fn query_point(&self, point : Simd<Pos, 2>) -> impl Iterator<Item = &'life T> + '_ {
+        self.query(move |boundary| boundary.contains(&point))
+    }
+
+    /// The items whose boundary overlaps `boundary`, in no particular order.
+    pub fn query_boundary(&self, boundary : Boundary) -> impl Iterator<Item = &'life T> + '_ {
+        self.query(move |other| other.does_overlap(&boundary))
+    }
+}
+
+/// Iterator over the items of an `RTree` that pass a test on their boundaries.
+pub struct RTreeQuery<'tree, 'life, T : Bounded, F : Fn(&Boundary) -> bool> {
+    tree : &'tree RTree<'life, T>,
+    nodes : Vec<usize>,
+    items : std::slice::Iter<'tree, &'life T>,
+    test : F
+}
+
+impl <'life, T : Bounded, F : Fn(&Boundary) -> bool> Iterator for RTreeQuery<'_, 'life, T, F> {
+    type Item = &'life T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                if (self.test)(item.boundary()) {
+                    return Some(*item);
+                }
+                continue;
+            }
+            let node = &self.tree.nodes[self.nodes.pop()?];
+            if node.is_leaf {
+                self.items = self.tree.items[node.first..node.first + node.count].iter();
+            } else {
+                let test = &self.test;
+                self.nodes.extend((node.first..node.first + node.count).filter(|child| test(&self.tree.nodes[*child].boundary)));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item {
+        boundary : Boundary
+    }
+
+    impl Bounded for Item {
+        fn boundary(&self) -> &Boundary {
+            &self.boundary
+        }
+    }
+
+    /// Boxes of all sizes over the unit square, some of them points, the same ones for every `seed`.
+    fn items(count : usize, seed : u32) -> Vec<Item> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as Pos / (1 << 24) as Pos
+        };
+        (0..count).map(|item| {
+            let corner_min = Simd::from_array([next(), next()]);
+            let size = if item % 7 == 0 { Simd::splat(0.0) } else { Simd::from_array([next(), next()]) * Simd::splat(0.2) };
+            Item { boundary : Boundary { corner_max : corner_min + size, corner_min } }
+        }).collect()
+    }
+
+    fn addresses<'a>(items : impl Iterator<Item = &'a Item>) -> Vec<usize> {
+        let mut addresses : Vec<usize> = items.map(|item| item as *const Item as usize).collect();
+        addresses.sort_unstable();
+        addresses
+    }
+
+    #[test]
+    fn queries_match_a_linear_scan() {
+        for count in [0, 1, NODE_CAPACITY, NODE_CAPACITY + 1, 1000] {
+            let items = items(count, 0x9e37_79b9);
+            let tree = RTree::new(&items);
+            for probe in self::items(200, 0x85eb_ca6b) {
+                let point = probe.boundary.corner_min;
+                assert_eq!(addresses(tree.query_point(point)), addresses(items.iter().filter(|item| item.boundary.contains(&point))));
+                let boundary = probe.boundary;
+                let scanned = addresses(items.iter().filter(|item| item.boundary.does_overlap(&boundary)));
+                assert_eq!(addresses(tree.query_boundary(boundary)), scanned);
+            }
+            for item in &items {
+                assert!(tree.query_point(item.boundary.corner_max).any(|found| std::ptr::eq(found, item)));
+            }
+        }
+    }
+}
@@ -1,14 +1,23 @@
 use std::simd::Simd;
+use crate::objects::boundary::Boundary;
 use crate::types::{Index, Pos};
 
 pub trait Positional {
     fn position(&self) -> &Simd<Pos, 2>;
 }
 
+pub trait Bounded {
+    fn boundary(&self) -> &Boundary;
+}
+
 pub trait Indexable {
     fn index(&self) -> Index;
 }
 
 pub trait ByteConvertable {
     fn from_bytes(byte_array : &[u8]) -> Self;
+}
+
+pub trait ByteWritable {
+    fn to_bytes(&self, byte_array : &mut Vec<u8>);
 }
\ No newline at end of file